@@ -0,0 +1,116 @@
+use alloc::vec::Vec;
+use core::{
+	fmt,
+	future::Future,
+	mem,
+	pin::Pin,
+	task::{Context, Poll},
+};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+#[cfg(feature = "std")]
+use crate::timer::Sleep;
+use crate::{RequestError, Subscription};
+
+/// Future collecting a number of resources, see
+/// [`Customer::request_many`](crate::Customer::request_many)
+///
+/// The future is [Unpin], so it can be polled without boxing
+#[must_use = "dropping the request leaves the queue"]
+pub struct ManyFuture<T, Q = ()> {
+	subscription: Subscription<T, Q>,
+	resources: Vec<T>,
+	n: usize,
+}
+
+impl<T, Q> ManyFuture<T, Q> {
+	pub(crate) fn new(subscription: Subscription<T, Q>, n: usize) -> Self {
+		Self { subscription, resources: Vec::new(), n }
+	}
+}
+
+// The resources are never pinned, only moved out of the future
+impl<T, Q> Unpin for ManyFuture<T, Q> {}
+
+impl<T, Q> Future for ManyFuture<T, Q> {
+	type Output = Result<Vec<T>, RequestError>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let this = &mut *self;
+
+		while this.resources.len() < this.n {
+			match this.subscription.poll_resource(cx) {
+				Poll::Ready(Ok(resource)) => this.resources.push(resource),
+				Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+				Poll::Pending => return Poll::Pending,
+			}
+		}
+
+		Poll::Ready(Ok(mem::take(&mut this.resources)))
+	}
+}
+
+impl<T, Q> fmt::Debug for ManyFuture<T, Q> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ManyFuture").field("collected", &self.resources.len()).field("n", &self.n).finish_non_exhaustive()
+	}
+}
+
+/// Future collecting resources until the deadline, see
+/// [`Customer::request_window`](crate::Customer::request_window)
+///
+/// The future is [Unpin], so it can be polled without boxing
+#[cfg(feature = "std")]
+#[must_use = "dropping the request leaves the queue"]
+pub struct WindowFuture<T, Q = ()> {
+	subscription: Subscription<T, Q>,
+	resources: Vec<T>,
+	sleep: Option<Sleep>,
+	closed: bool,
+}
+
+#[cfg(feature = "std")]
+impl<T, Q> WindowFuture<T, Q> {
+	pub(crate) fn new(subscription: Subscription<T, Q>, deadline: Option<Instant>, closed: bool) -> Self {
+		Self { subscription, resources: Vec::new(), sleep: deadline.map(Sleep::until), closed }
+	}
+}
+
+// The resources are never pinned, only moved out of the future
+#[cfg(feature = "std")]
+impl<T, Q> Unpin for WindowFuture<T, Q> {}
+
+#[cfg(feature = "std")]
+impl<T, Q> Future for WindowFuture<T, Q> {
+	type Output = Result<Vec<T>, RequestError>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let this = &mut *self;
+
+		if this.closed {
+			return Poll::Ready(Err(RequestError::Closed));
+		}
+
+		loop {
+			match this.subscription.poll_resource(cx) {
+				Poll::Ready(Ok(resource)) => this.resources.push(resource),
+				Poll::Ready(Err(RequestError::Closed)) => return Poll::Ready(Ok(mem::take(&mut this.resources))),
+				Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+				Poll::Pending => break,
+			}
+		}
+
+		match &mut this.sleep {
+			Some(sleep) => Pin::new(sleep).poll(cx).map(|()| Ok(mem::take(&mut this.resources))),
+			None => Poll::Pending,
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T, Q> fmt::Debug for WindowFuture<T, Q> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("WindowFuture").field("collected", &self.resources.len()).finish_non_exhaustive()
+	}
+}
@@ -0,0 +1,36 @@
+use std::{
+	future::Future,
+	pin::pin,
+	sync::Arc,
+	task::{Context, Poll, Wake, Waker},
+	thread::{self, Thread},
+};
+
+/// Wakes the blocked thread by unparking it
+struct Unparker(Thread);
+
+impl Wake for Unparker {
+	fn wake(self: Arc<Self>) {
+		self.0.unpark();
+	}
+
+	fn wake_by_ref(self: &Arc<Self>) {
+		self.0.unpark();
+	}
+}
+
+/// Runs the future to completion on the current thread,
+/// parking it while the future is pending
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+	let mut future = pin!(future);
+	let waker = Waker::from(Arc::new(Unparker(thread::current())));
+	let mut cx = Context::from_waker(&waker);
+
+	loop {
+		if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+			return output;
+		}
+
+		thread::park();
+	}
+}
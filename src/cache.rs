@@ -0,0 +1,65 @@
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/// The moment a resource got cached, only tracked with the `std` feature
+#[cfg(feature = "std")]
+type Stamp = Instant;
+#[cfg(not(feature = "std"))]
+#[derive(Debug, Clone, Copy)]
+struct Stamp;
+
+/// The last resource sent by a [Vendor](crate::Vendor), handed out to
+/// customers until it gets too old
+#[derive(Debug)]
+pub(crate) struct Cache<T> {
+	entry: Option<(T, Stamp)>,
+	max_age: Option<Duration>,
+	clone: fn(&T) -> T,
+}
+
+impl<T> Cache<T> {
+	pub(crate) fn new(max_age: Option<Duration>) -> Self
+	where
+		T: Clone,
+	{
+		Self { entry: None, max_age, clone: T::clone }
+	}
+
+	pub(crate) fn store(&mut self, resource: T) {
+		#[cfg(feature = "std")]
+		let stamp = Instant::now();
+		#[cfg(not(feature = "std"))]
+		let stamp = Stamp;
+
+		self.entry = Some((resource, stamp));
+	}
+
+	/// Returns a copy of the cached resource unless it has expired
+	pub(crate) fn get(&self) -> Option<T> {
+		let (resource, stored_at) = self.entry.as_ref()?;
+
+		if is_expired(*stored_at, self.max_age) {
+			return None;
+		}
+
+		Some((self.clone)(resource))
+	}
+
+	/// Same as [`Cache::get`], but only if the cache has a max age,
+	/// so a resource cached for good never answers a request
+	pub(crate) fn get_fresh(&self) -> Option<T> {
+		self.max_age?;
+		self.get()
+	}
+}
+
+#[cfg(feature = "std")]
+fn is_expired(stored_at: Stamp, max_age: Option<Duration>) -> bool {
+	max_age.is_some_and(|max_age| stored_at.elapsed() > max_age)
+}
+
+#[cfg(not(feature = "std"))]
+fn is_expired(_: Stamp, _: Option<Duration>) -> bool {
+	false
+}
@@ -1,33 +1,69 @@
-use std::{ops::Deref, sync::Arc};
+//! Take a queue for a resource: [Customer]s request it, a [Vendor] sends it
+//! to everyone waiting.
+//!
+//! # Features
+//! - `std` (default): blocking requests, timeouts and cache expiry. Without it
+//!   the crate only needs `alloc`.
+//! - `critical-section`: without `std`, guards the internal state with the
+//!   platform's [critical section](https://docs.rs/critical-section),
+//!   so vendors and customers can be used from interrupt handlers.
+//!
+//! Without either of them the internal state is guarded by a spin lock.
+//! On a single core, an interrupt handler using a vendor or a customer while
+//! the interrupted code holds that lock spins forever, so firmware sending
+//! from interrupts must enable `critical-section`
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use concurrent_queue::{ConcurrentQueue, PushError};
-use onetime::{channel, RecvError, Sender};
-use thiserror::Error;
+extern crate alloc;
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
 
-#[derive(Debug)]
-struct Waiters<T>(ConcurrentQueue<Sender<T>>);
-
-impl<T> Default for Waiters<T> {
-	fn default() -> Self {
-		Self(ConcurrentQueue::unbounded())
-	}
-}
+mod batch;
+#[cfg(feature = "std")]
+mod block;
+mod cache;
+mod oneshot;
+mod request;
+mod single_flight;
+mod subscription;
+mod sync;
+mod ticket;
+#[cfg(feature = "std")]
+mod timer;
+mod topic;
+mod waiters;
 
-impl<T> Deref for Waiters<T> {
-	type Target = ConcurrentQueue<Sender<T>>;
+use alloc::{boxed::Box, sync::Arc};
+use core::{error::Error as StdError, fmt, future::Future};
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
 
-	fn deref(&self) -> &Self::Target {
-		&self.0
-	}
-}
+pub use crate::{
+	batch::ManyFuture,
+	request::{OwnedRequestFuture, RequestBuilder, RequestFuture},
+	single_flight::SingleFlight,
+	subscription::{Buffer, Subscription},
+	ticket::Ticket,
+	topic::{TopicCustomer, TopicRequestFuture, TopicVendor},
+	waiters::Overflow,
+};
+#[cfg(feature = "std")]
+pub use crate::{batch::WindowFuture, request::TimeoutFuture};
+use crate::{
+	oneshot::RecvError,
+	waiters::{Drained, Waiters},
+};
 
 /// Customer can request resource from the linked [Vendor]
+///
+/// Customers may pass a query of type `Q` along with the request,
+/// so the vendor can answer each of them differently, see [`Vendor::serve`]
 #[derive(Debug)]
-pub struct Customer<T> {
-	waiters: Arc<Waiters<T>>,
+pub struct Customer<T, Q = ()> {
+	waiters: Arc<Waiters<T, Q>>,
 }
 
-impl<T> Clone for Customer<T> {
+impl<T, Q> Clone for Customer<T, Q> {
 	fn clone(&self) -> Self {
 		Self { waiters: self.waiters.clone() }
 	}
@@ -36,31 +72,236 @@ impl<T> Clone for Customer<T> {
 impl<T> Customer<T> {
 	/// Queuing up for a resource
 	///
+	/// If the linked [Vendor] keeps a cache with a max age, a fresh enough
+	/// cached resource is returned right away, see
+	/// [`Vendor::with_cache_max_age`]. This is the only request reading the
+	/// cache, and a cache without a max age is only read by
+	/// [`Customer::latest`].
+	///
+	/// The request is queued as soon as it is created, see [`RequestFuture`].
+	/// Dropping the returned future leaves the queue,
+	/// so cancelled requests are not counted as waiters.
+	///
+	/// # Errors
+	/// You may get an error if you failed to queue or take a resource,
+	/// or [`RequestError::Closed`] if the linked [Vendor]s are closed or gone.
+	pub fn request(&self) -> RequestFuture<'_, T> {
+		RequestFuture::new(self, (), 0, None, true)
+	}
+
+	/// Same as [`Customer::request`], but the returned future owns the
+	/// customer, so it is not bound to its lifetime
+	pub fn into_request(self) -> OwnedRequestFuture<T> {
+		OwnedRequestFuture::new(self, (), true)
+	}
+
+	/// Queuing up for the next sent resource, never taken from the cache
+	///
+	/// # Errors
+	/// Same as [`Customer::request`]
+	pub fn request_fresh(&self) -> RequestFuture<'_, T> {
+		RequestFuture::new(self, (), 0, None, false)
+	}
+
+	/// Queuing up for the next sent resource ahead of every request with
+	/// a lower priority, requests of the same priority keep their order.
+	/// Plain requests have the priority of zero
+	///
+	/// Priorities matter when the resource is scarce, i.e. with
+	/// [`Vendor::send_one`] or when the queue is bounded.
+	/// The resource is never taken from the cache
+	///
+	/// # Errors
+	/// Same as [`Customer::request`]
+	pub fn request_with_priority(&self, priority: i32) -> RequestFuture<'_, T> {
+		RequestFuture::new(self, (), priority, None, false)
+	}
+
+	/// Takes a place in the queue right away, without awaiting.
+	/// The returned [Ticket] is then checked or awaited for the resource,
+	/// which is never taken from the cache
+	///
+	/// # Errors
+	/// Same as [`Customer::request`], but a bounded queue with
+	/// [`Overflow::Wait`] fails with [`RequestError::Full`] instead of waiting
+	pub fn enqueue(&self) -> Result<Ticket<T>, RequestError> {
+		self.request_builder().register()
+	}
+
+	/// Starts building a request, which is queued once registered
+	pub fn request_builder(&self) -> RequestBuilder<'_, T> {
+		RequestBuilder::new(self, ())
+	}
+
+	/// Queuing up for the first sent resource satisfying the predicate,
+	/// resources not satisfying it are skipped.
+	///
+	/// The predicate is evaluated by the [Vendor] while sending, with the
+	/// queue locked, so it should be cheap and must use neither the vendor
+	/// nor any of its customers, e.g. [`Customer::latest`], as that deadlocks.
+	/// Resources built by [`Vendor::send_with`] are never offered,
+	/// nor is the cached resource
+	///
+	/// # Errors
+	/// Same as [`Customer::request`]
+	pub fn request_where<F>(&self, predicate: F) -> RequestFuture<'_, T>
+	where
+		F: Fn(&T) -> bool + Send + 'static,
+	{
+		RequestFuture::new(self, (), 0, Some(Box::new(predicate)), false)
+	}
+
+	/// Queuing up for a resource, giving up after the given duration
+	///
+	/// Same as [`Customer::request`], the request is queued as soon as
+	/// it is created
+	///
+	/// # Errors
+	/// Same as [`Customer::request`], plus [`RequestError::Timeout`]
+	/// if the resource was not sent in time.
+	#[cfg(feature = "std")]
+	pub fn request_timeout(&self, duration: Duration) -> TimeoutFuture<'_, T> {
+		TimeoutFuture::new(self.request(), Instant::now().checked_add(duration))
+	}
+
+	/// Queuing up for a resource, giving up once the deadline is reached
+	///
+	/// Same as [`Customer::request`], the request is queued as soon as
+	/// it is created
+	///
+	/// # Errors
+	/// Same as [`Customer::request`], plus [`RequestError::Timeout`]
+	/// if the resource was not sent in time.
+	#[cfg(feature = "std")]
+	pub fn request_until(&self, deadline: Instant) -> TimeoutFuture<'_, T> {
+		TimeoutFuture::new(self.request(), Some(deadline))
+	}
+
+	/// Same as [`Customer::request`], but parks the current thread instead of
+	/// awaiting, so it can be used outside of async code.
+	///
+	/// Must not be called from within an async runtime, as it blocks
+	///
+	/// # Errors
+	/// Same as [`Customer::request`]
+	#[cfg(feature = "std")]
+	pub fn request_blocking(&self) -> Result<T, RequestError> {
+		block::block_on(self.request())
+	}
+
+	/// Same as [`Customer::request_timeout`], but parks the current thread
+	/// instead of awaiting
+	///
+	/// # Errors
+	/// Same as [`Customer::request_timeout`]
+	#[cfg(feature = "std")]
+	pub fn request_blocking_timeout(&self, duration: Duration) -> Result<T, RequestError> {
+		block::block_on(self.request_timeout(duration))
+	}
+}
+
+impl<T, Q> Customer<T, Q> {
+	/// Stays in the queue until `n` resources are sent, resolving with all of
+	/// them in the order they were sent
+	///
+	/// The customer joins the queue as soon as the future is created, but
+	/// waits as a [Subscription] rather than a request, so it is not counted
+	/// against the capacity of a bounded [Vendor] nor subject to its
+	/// [Overflow] policy, and it is never served by [`Vendor::send_one`]
+	/// or [`Vendor::serve`]
+	///
+	/// # Errors
+	/// [`RequestError::Closed`] if the linked [Vendor]s are closed or gone
+	/// before `n` resources are sent, or [`RequestError::Producer`] if
+	/// the vendor fails to produce one of them, see [`Vendor::send_error`]
+	pub fn request_many(&self, n: usize) -> ManyFuture<T, Q> {
+		ManyFuture::new(self.subscribe_with(Buffer::DropNewest(n)), n)
+	}
+
+	/// Collects every resource sent during the given duration,
+	/// stopping early if the linked [Vendor]s are closed or gone
+	///
+	/// The window starts and the customer joins the queue as soon as
+	/// the future is created. Same as [`Customer::request_many`], it waits
+	/// as a [Subscription], so it is not counted against the capacity of a
+	/// bounded [Vendor] nor served by [`Vendor::send_one`] or [`Vendor::serve`]
+	///
+	/// # Errors
+	/// [`RequestError::Closed`] if the linked [Vendor]s are already
+	/// closed or gone, or [`RequestError::Producer`] if the vendor fails
+	/// to produce within the window, see [`Vendor::send_error`]
+	#[cfg(feature = "std")]
+	pub fn request_window(&self, duration: Duration) -> WindowFuture<T, Q> {
+		let subscription = self.subscribe_with(Buffer::Unbounded);
+		WindowFuture::new(subscription, Instant::now().checked_add(duration), self.is_closed())
+	}
+
+	/// Same as [`Customer::request_builder`], for a request passing the query
+	pub fn query_builder(&self, query: Q) -> RequestBuilder<'_, T, Q> {
+		RequestBuilder::new(self, query)
+	}
+
+	/// Queuing up for a resource made for the given query
+	///
+	/// The query is answered by [`Vendor::serve`], while resources sent by
+	/// other means are delivered regardless of the query.
+	/// The resource is never taken from the cache
+	///
 	/// # Errors
-	/// You may get an error if you failed to queue or take a resource.
-	pub async fn request(&self) -> Result<T, RequestError> {
-		let (tx, rx) = channel();
-		self.waiters.push(tx)?;
-		rx.recv().await.map_err(Into::into)
+	/// Same as [`Customer::request`]
+	pub fn query(&self, query: Q) -> RequestFuture<'_, T, Q> {
+		RequestFuture::new(self, query, 0, None, false)
+	}
+
+	/// Subscribes to every resource sent from now on, keeping only the
+	/// latest one while the subscription is not polled, see [`Buffer`]
+	///
+	/// The subscription counts as a waiter for as long as it lives,
+	/// but not against the capacity of a bounded [Vendor]
+	pub fn subscribe(&self) -> Subscription<T, Q> {
+		self.subscribe_with(Buffer::default())
+	}
+
+	/// Same as [`Customer::subscribe`], but with the given buffering policy
+	pub fn subscribe_with(&self, buffer: Buffer) -> Subscription<T, Q> {
+		Subscription::new(self.waiters.clone(), buffer)
+	}
+
+	/// Returns the last sent resource without waiting, if the linked [Vendor]
+	/// keeps a cache and the resource has not expired yet
+	pub fn latest(&self) -> Option<T> {
+		self.waiters.cached()
+	}
+
+	/// Returns `true` if the linked [Vendor]s have been closed or dropped,
+	/// so no resource will ever be sent
+	pub fn is_closed(&self) -> bool {
+		self.waiters.is_closed()
 	}
 }
 
 /// Vendor can send the resource to waiting [Customer]s.
 /// If there is no waiting [Customer]s, resource will be lost
 #[derive(Debug)]
-pub struct Vendor<T> {
-	waiters: Arc<Waiters<T>>,
+pub struct Vendor<T, Q = ()> {
+	waiters: Arc<Waiters<T, Q>>,
 }
 
-impl<T> Clone for Vendor<T> {
+impl<T, Q> Clone for Vendor<T, Q> {
 	fn clone(&self) -> Self {
-		Self { waiters: self.waiters.clone() }
+		Self::attach(self.waiters.clone())
 	}
 }
 
-impl<T> Default for Vendor<T> {
+impl<T, Q> Default for Vendor<T, Q> {
 	fn default() -> Self {
-		Self { waiters: Arc::default() }
+		Self::attach(Arc::default())
+	}
+}
+
+impl<T, Q> Drop for Vendor<T, Q> {
+	fn drop(&mut self) {
+		self.waiters.detach_vendor();
 	}
 }
 
@@ -69,33 +310,190 @@ impl<T> Vendor<T> {
 		Self::default()
 	}
 
+	/// Creates a vendor whose queue holds at most `capacity` waiting [Customer]s.
+	/// The `overflow` policy decides what happens to customers beyond that
+	///
+	/// # Panics
+	/// If the capacity is zero
+	pub fn bounded(capacity: usize, overflow: Overflow) -> Self {
+		Self::queried_bounded(capacity, overflow)
+	}
+}
+
+impl<T, Q> Vendor<T, Q> {
+	/// Creates a vendor whose customers pass a query of type `Q`
+	/// along with their requests, see [`Vendor::serve`]
+	pub fn queried() -> Self {
+		Self::default()
+	}
+
+	/// Same as [`Vendor::bounded`], for a vendor whose customers pass
+	/// a query along with their requests
+	///
+	/// # Panics
+	/// If the capacity is zero
+	pub fn queried_bounded(capacity: usize, overflow: Overflow) -> Self {
+		assert!(capacity > 0, "capacity must be positive");
+		Self::attach(Arc::new(Waiters::bounded(capacity, overflow)))
+	}
+
+	/// Makes the vendor remember the last sent resource, so customers
+	/// can read it with [`Customer::latest`] without waiting for the next one.
+	///
+	/// Requests still wait for the next resource, as the cached one may be
+	/// stale by then, see [`Vendor::with_cache_max_age`]
+	#[must_use]
+	pub fn with_cache(self) -> Self
+	where
+		T: Clone,
+	{
+		self.waiters.enable_cache(None);
+		self
+	}
+
+	/// Same as [`Vendor::with_cache`], but the cached resource is only handed
+	/// out until it is older than `max_age`.
+	///
+	/// Meanwhile it also answers [`Customer::request`] right away,
+	/// as the resource is known to be fresh enough
+	#[cfg(feature = "std")]
+	#[must_use]
+	pub fn with_cache_max_age(self, max_age: Duration) -> Self
+	where
+		T: Clone,
+	{
+		self.waiters.enable_cache(Some(max_age));
+		self
+	}
+
+	fn attach(waiters: Arc<Waiters<T, Q>>) -> Self {
+		waiters.attach_vendor();
+		Self { waiters }
+	}
+
 	/// Creates a customer linked to this [Vendor]
-	pub fn customer(&self) -> Customer<T> {
+	pub fn customer(&self) -> Customer<T, Q> {
 		Customer { waiters: self.waiters.clone() }
 	}
 
 	/// Sends the resource to customers
 	///
-	/// Note, if no one is in the queue the resource will be lost
-	pub fn send(&self, resource: T)
+	/// Every customer queued at the moment of the call is served exactly once,
+	/// customers arriving meanwhile wait for the next resource.
+	///
+	/// Note, if no one is in the queue the resource will be lost,
+	/// which can be checked with [`SendReport::is_lost`]
+	pub fn send(&self, resource: T) -> SendReport
 	where
 		T: Clone,
 	{
-		if self.waiters_count() == 1 {
-			if let Ok(waiter) = self.waiters.pop() {
-				let _ = waiter.send(resource);
-			}
-		} else {
-			for _ in 0..self.waiters_count() - 1 {
-				if let Ok(waiter) = self.waiters.pop() {
-					let _ = waiter.send(resource.clone());
-				}
+		let mut report = SendReport::default();
+		self.waiters.remember(|| resource.clone());
+		let Drained { requests, subscribers } = self.waiters.drain(Some(&resource));
+		let mut requests = requests.into_iter().map(|(_, responder)| responder);
+
+		for feed in subscribers {
+			report.record_buffered(feed.push(resource.clone()));
+		}
+
+		if let Some(last) = requests.next_back() {
+			for waiter in requests {
+				report.record(waiter.send(Ok(resource.clone())).is_ok());
 			}
 
-			if let Ok(waiter) = self.waiters.pop() {
-				let _ = waiter.send(resource);
+			report.record(last.send(Ok(resource)).is_ok());
+		}
+
+		report
+	}
+
+	/// Sends a resource built by `factory` to every customer,
+	/// so the resource does not have to be [Clone].
+	/// The factory is only called for customers actually in the queue,
+	/// plus once more for the cache if it is enabled.
+	///
+	/// Customers waiting with [`Customer::request_where`] are skipped,
+	/// as the resource is unknown until it is built
+	pub fn send_with<F>(&self, mut factory: F) -> SendReport
+	where
+		F: FnMut() -> T,
+	{
+		let mut report = SendReport::default();
+		self.waiters.remember(&mut factory);
+		let Drained { requests, subscribers } = self.waiters.drain(None);
+
+		for feed in subscribers {
+			report.record_buffered(feed.push(factory()));
+		}
+
+		for (_, waiter) in requests {
+			report.record(waiter.send(Ok(factory())).is_ok());
+		}
+
+		report
+	}
+
+	/// Answers every queued customer with a resource made for its query.
+	/// Customers waiting with [`Customer::request_where`] and subscriptions
+	/// are skipped, as they have no query to answer
+	pub fn serve<F>(&self, mut answer: F) -> SendReport
+	where
+		F: FnMut(&Q) -> T,
+	{
+		let mut report = SendReport::default();
+		let Drained { requests, .. } = self.waiters.drain(None);
+
+		for (query, waiter) in requests {
+			report.record(waiter.send(Ok(answer(&query))).is_ok());
+		}
+
+		report
+	}
+
+	/// Wakes every queued customer with [`RequestError::Producer`],
+	/// so they do not wait for a resource that failed to be produced.
+	/// Subscriptions end with the same error, see [`Subscription::error`].
+	///
+	/// The queue stays open, later requests and sends are served as usual
+	pub fn send_error<E>(&self, error: E) -> SendReport
+	where
+		E: StdError + Send + Sync + 'static,
+	{
+		let mut report = SendReport::default();
+		let error = RequestError::Producer(Arc::new(error));
+		let Drained { requests, subscribers } = self.waiters.take_all();
+
+		for feed in subscribers {
+			feed.end(error.clone());
+			report.record(true);
+		}
+
+		for (_, waiter) in requests {
+			report.record(waiter.send(Err(error.clone())).is_ok());
+		}
+
+		report
+	}
+
+	/// Hands the resource over to the first customer in the queue accepting it
+	/// only, see [`Customer::request_with_priority`].
+	/// The resource is never cached nor given to subscriptions,
+	/// so it can not reach anyone else
+	///
+	/// # Errors
+	/// Returns the resource back if no one is in the queue
+	pub fn send_one(&self, mut resource: T) -> Result<(), T> {
+		while let Some(waiter) = self.waiters.pop(&resource) {
+			match waiter.send(Ok(resource)) {
+				Ok(()) => return Ok(()),
+				Err(err) => {
+					let Ok(returned) = err else { unreachable!("waiters are only sent resources") };
+					resource = returned;
+				}
 			}
 		}
+
+		Err(resource)
 	}
 
 	pub fn waiters_count(&self) -> usize {
@@ -107,18 +505,151 @@ impl<T> Vendor<T> {
 	pub fn has_waiters(&self) -> bool {
 		self.waiters_count() > 0
 	}
+
+	/// Waits until someone is in the queue, so the resource is only produced
+	/// on demand instead of polling [`Vendor::has_waiters`].
+	///
+	/// Returns `false` if the queue was closed instead
+	pub async fn wait_for_waiters(&self) -> bool {
+		self.wait_for_n(1).await
+	}
+
+	/// Runs the producer every time someone is in the queue and sends its
+	/// result, until the queue is closed.
+	///
+	/// Customers arriving while the resource is being produced get the same
+	/// resource, so concurrent requests never cause duplicate work.
+	/// If nobody takes the resource, e.g. only [`Customer::request_where`]
+	/// requests rejecting it are queued, the producer waits for someone new
+	/// to arrive before running again.
+	///
+	/// A [Subscription] wants every resource, so the producer runs
+	/// back to back for as long as one is alive
+	pub async fn serve_with<F, Fut>(&self, mut produce: F)
+	where
+		T: Clone,
+		F: FnMut() -> Fut,
+		Fut: Future<Output = T>,
+	{
+		while self.wait_for_waiters().await {
+			let arrivals = self.waiters.arrivals();
+			let resource = produce().await;
+
+			if self.send(resource).is_lost() && !self.waiters.wait_for_arrival(arrivals).await {
+				break;
+			}
+		}
+	}
+
+	/// Waits until at least `n` customers are in the queue
+	///
+	/// Returns `false` if the queue was closed instead
+	pub async fn wait_for_n(&self, n: usize) -> bool {
+		self.waiters.wait_for(n).await
+	}
+
+	/// Closes the queue, waking all waiting [Customer]s with
+	/// [`RequestError::Closed`]. Any further requests fail the same way.
+	///
+	/// Returns `true` if this call closed the queue
+	pub fn close(&self) -> bool {
+		self.waiters.close()
+	}
+
+	pub fn is_closed(&self) -> bool {
+		self.waiters.is_closed()
+	}
+}
+
+/// [Vendor] delivering a single shared allocation of the resource to all
+/// customers, see [`Vendor::send_shared`]
+pub type SharedVendor<T> = Vendor<Arc<T>>;
+
+/// [Customer] of a [`SharedVendor`]
+pub type SharedCustomer<T> = Customer<Arc<T>>;
+
+impl<T, Q> Vendor<Arc<T>, Q> {
+	/// Wraps the resource into a single [Arc] shared by all customers,
+	/// so the resource does not have to be [Clone]
+	pub fn send_shared(&self, resource: T) -> SendReport {
+		self.send(Arc::new(resource))
+	}
+}
+
+/// Outcome of [`Vendor::send`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SendReport {
+	/// Number of [Customer]s that received the resource
+	pub delivered: usize,
+	/// Number of [Customer]s that stopped waiting before the resource reached
+	/// them
+	pub dropped_receivers: usize,
+	/// Number of [Subscription]s that discarded the resource because their
+	/// buffer is full
+	pub discarded: usize,
+}
+
+impl SendReport {
+	/// Returns `true` if nobody received the resource
+	pub fn is_lost(&self) -> bool {
+		self.delivered == 0
+	}
+
+	fn record(&mut self, delivered: bool) {
+		if delivered {
+			self.delivered += 1;
+		} else {
+			self.dropped_receivers += 1;
+		}
+	}
+
+	fn record_buffered(&mut self, buffered: bool) {
+		if buffered {
+			self.delivered += 1;
+		} else {
+			self.discarded += 1;
+		}
+	}
 }
 
-#[derive(Debug, Error)]
-#[error("failed to request resource")]
+#[derive(Debug, Clone)]
 pub enum RequestError {
-	Push,
+	/// The [Vendor] dropped the request without answering it,
+	/// which only happens if it panicked while sending
 	Recv,
+	/// The resource was not sent in time
+	Timeout,
+	/// The linked [Vendor]s are closed or gone
+	Closed,
+	/// The bounded queue is full, see [`Overflow::Reject`]
+	Full,
+	/// The request was pushed out of the bounded queue,
+	/// see [`Overflow::EvictOldest`]
+	Evicted,
+	/// The [Vendor] failed to produce the resource,
+	/// see [`Vendor::send_error`]
+	Producer(Arc<dyn StdError + Send + Sync>),
 }
 
-impl<T> From<PushError<T>> for RequestError {
-	fn from(_: PushError<T>) -> Self {
-		Self::Push
+impl fmt::Display for RequestError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::Recv => "vendor dropped the request",
+			Self::Timeout => "timed out waiting for resource",
+			Self::Closed => "vendor is closed",
+			Self::Full => "queue is full",
+			Self::Evicted => "evicted from the queue",
+			Self::Producer(_) => "vendor failed to produce resource",
+		})
+	}
+}
+
+impl StdError for RequestError {
+	fn source(&self) -> Option<&(dyn StdError + 'static)> {
+		match self {
+			Self::Producer(error) => Some(&**error),
+			_ => None,
+		}
 	}
 }
 
@@ -130,6 +661,20 @@ impl From<RecvError> for RequestError {
 
 #[cfg(test)]
 mod tests {
+	use std::{
+		format,
+		pin::Pin,
+		string::{String, ToString},
+		sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+		task::{Context, Wake, Waker},
+		time::Duration,
+		vec,
+		vec::Vec,
+	};
+
+	use futures_core::FusedFuture;
+	use smol::{future, stream::StreamExt};
+
 	use super::*;
 
 	#[test]
@@ -156,4 +701,918 @@ mod tests {
 			t3.await;
 		});
 	}
+
+	#[test]
+	fn send_report() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			assert!(vendor.send("lost").is_lost());
+
+			let mut request = Box::pin(customer.request());
+			assert!(future::poll_once(request.as_mut()).await.is_none());
+
+			let report = vendor.send("ok");
+			assert_eq!(report, SendReport { delivered: 1, dropped_receivers: 0, discarded: 0 });
+			assert!(matches!(request.await, Ok("ok")));
+		});
+	}
+
+	#[test]
+	fn concurrent_sends() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+			let subscription = customer.subscribe_with(Buffer::DropNewest(400));
+
+			let threads: Vec<_> = (0..4)
+				.map(|_| {
+					let vendor = vendor.clone();
+					std::thread::spawn(move || (0..100).map(|frame| vendor.send(frame).delivered).sum::<usize>())
+				})
+				.collect();
+
+			let delivered: usize = threads.into_iter().map(|thread| thread.join().unwrap_or_default()).sum();
+			assert_eq!(delivered, 400);
+
+			drop(vendor);
+			assert_eq!(subscription.count().await, 400);
+		});
+	}
+
+	#[test]
+	fn send_with() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let mut first = Box::pin(customer.request());
+			let mut second = Box::pin(customer.request());
+			assert!(future::poll_once(first.as_mut()).await.is_none());
+			assert!(future::poll_once(second.as_mut()).await.is_none());
+
+			let mut built = 0;
+			let report = vendor.send_with(|| {
+				built += 1;
+				built
+			});
+
+			assert_eq!(built, 2);
+			assert_eq!(report.delivered, 2);
+			assert!(matches!(first.await, Ok(1)));
+			assert!(matches!(second.await, Ok(2)));
+		});
+	}
+
+	#[test]
+	fn send_shared() {
+		struct Frame;
+
+		smol::block_on(async move {
+			let vendor = SharedVendor::new();
+			let customer = vendor.customer();
+
+			let mut first = Box::pin(customer.request());
+			let mut second = Box::pin(customer.request());
+			assert!(future::poll_once(first.as_mut()).await.is_none());
+			assert!(future::poll_once(second.as_mut()).await.is_none());
+
+			vendor.send_shared(Frame);
+
+			let (Ok(first), Ok(second)) = (first.await, second.await) else { panic!("frame is not delivered") };
+			assert!(Arc::ptr_eq(&first, &second));
+		});
+	}
+
+	#[test]
+	fn send_one() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			assert!(matches!(vendor.send_one("job"), Err("job")));
+
+			let mut first = Box::pin(customer.request());
+			let mut second = Box::pin(customer.request());
+			assert!(future::poll_once(first.as_mut()).await.is_none());
+			assert!(future::poll_once(second.as_mut()).await.is_none());
+
+			assert!(vendor.send_one("job").is_ok());
+			assert!(matches!(first.await, Ok("job")));
+			assert!(future::poll_once(second.as_mut()).await.is_none());
+			assert_eq!(vendor.waiters_count(), 1);
+		});
+	}
+
+	#[test]
+	fn request_where() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let mut even = Box::pin(customer.request_where(|n| n % 2 == 0));
+			let mut any = Box::pin(customer.request());
+			assert!(future::poll_once(even.as_mut()).await.is_none());
+			assert!(future::poll_once(any.as_mut()).await.is_none());
+
+			assert_eq!(vendor.send(1).delivered, 1);
+			assert!(matches!(any.await, Ok(1)));
+			assert!(future::poll_once(even.as_mut()).await.is_none());
+
+			assert!(matches!(vendor.send_one(3), Err(3)));
+			assert!(vendor.send_one(4).is_ok());
+			assert!(matches!(even.await, Ok(4)));
+		});
+	}
+
+	#[test]
+	fn serve() {
+		smol::block_on(async move {
+			let vendor = Vendor::<String, u32>::queried();
+			let customer = vendor.customer();
+
+			let mut small = Box::pin(customer.query(320));
+			let mut large = Box::pin(customer.query(1920));
+			assert!(future::poll_once(small.as_mut()).await.is_none());
+			assert!(future::poll_once(large.as_mut()).await.is_none());
+
+			let report = vendor.serve(|width| format!("frame {width}"));
+			assert_eq!(report.delivered, 2);
+			assert_eq!(small.await.ok().as_deref(), Some("frame 320"));
+			assert_eq!(large.await.ok().as_deref(), Some("frame 1920"));
+		});
+	}
+
+	#[test]
+	fn subscribe() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let mut subscription = customer.subscribe_with(Buffer::Unbounded);
+			assert_eq!(vendor.waiters_count(), 1);
+
+			vendor.send(1);
+			vendor.send(2);
+			assert_eq!(vendor.waiters_count(), 1);
+			assert_eq!(subscription.next().await, Some(1));
+			assert_eq!(subscription.next().await, Some(2));
+
+			vendor.close();
+			assert_eq!(subscription.next().await, None);
+		});
+	}
+
+	#[test]
+	fn subscribe_keeps_latest() {
+		smol::block_on(async move {
+			let vendor = Vendor::bounded(1, Overflow::Reject);
+			let customer = vendor.customer();
+
+			let mut subscription = customer.subscribe();
+			let request = customer.request();
+			assert_eq!(vendor.waiters_count(), 2);
+
+			assert_eq!(vendor.send(1).discarded, 0);
+			vendor.send(2);
+			assert!(matches!(request.await, Ok(1)));
+			assert_eq!(subscription.next().await, Some(2));
+		});
+	}
+
+	#[test]
+	fn subscribe_drop_oldest() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let mut subscription = customer.subscribe_with(Buffer::DropOldest(1));
+			vendor.send(1);
+			vendor.send(2);
+			assert_eq!(subscription.next().await, Some(2));
+
+			drop(subscription);
+			assert!(!vendor.has_waiters());
+		});
+	}
+
+	#[test]
+	fn subscribe_drop_newest() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let mut subscription = customer.subscribe_with(Buffer::DropNewest(1));
+			vendor.send(1);
+			assert_eq!(vendor.send(2), SendReport { delivered: 0, dropped_receivers: 0, discarded: 1 });
+			assert_eq!(subscription.next().await, Some(1));
+		});
+	}
+
+	#[test]
+	fn request_many() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let mut burst = Box::pin(customer.request_many(3));
+			assert!(future::poll_once(burst.as_mut()).await.is_none());
+
+			for frame in 1..=3 {
+				vendor.send(frame);
+			}
+
+			assert_eq!(burst.await.ok(), Some(vec![1, 2, 3]));
+
+			let mut burst = Box::pin(customer.request_many(2));
+			let mut endless = Box::pin(customer.request_many(usize::MAX));
+			assert!(future::poll_once(burst.as_mut()).await.is_none());
+			assert!(future::poll_once(endless.as_mut()).await.is_none());
+			vendor.send(4);
+			vendor.close();
+			assert!(matches!(burst.await, Err(RequestError::Closed)));
+			assert!(matches!(endless.await, Err(RequestError::Closed)));
+		});
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn request_many_failed() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let mut burst = Box::pin(customer.request_many(2));
+			let mut window = Box::pin(customer.request_window(Duration::from_secs(5)));
+			assert!(future::poll_once(burst.as_mut()).await.is_none());
+			assert!(future::poll_once(window.as_mut()).await.is_none());
+
+			vendor.send(1);
+			vendor.send_error(std::io::Error::other("camera disconnected"));
+			assert!(matches!(burst.await, Err(RequestError::Producer(_))));
+			assert!(matches!(window.await, Err(RequestError::Producer(_))));
+			assert!(!vendor.has_waiters());
+		});
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn request_window() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let mut window = Box::pin(customer.request_window(Duration::from_millis(20)));
+			assert!(future::poll_once(window.as_mut()).await.is_none());
+
+			vendor.send(1);
+			vendor.send(2);
+			assert_eq!(window.await.ok(), Some(vec![1, 2]));
+			assert!(!vendor.has_waiters());
+		});
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn request_blocking() {
+		let vendor = Vendor::new();
+		let customer = vendor.customer();
+
+		let t1 = std::thread::spawn(move || customer.request_blocking());
+
+		while !vendor.has_waiters() {
+			std::thread::yield_now();
+		}
+
+		vendor.send("ok");
+		assert!(matches!(t1.join(), Ok(Ok("ok"))));
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn request_blocking_timeout() {
+		let vendor = Vendor::<()>::new();
+		let customer = vendor.customer();
+
+		let result = customer.request_blocking_timeout(Duration::from_millis(10));
+		assert!(matches!(result, Err(RequestError::Timeout)));
+	}
+
+	#[test]
+	fn cached() {
+		smol::block_on(async move {
+			let vendor = Vendor::new().with_cache();
+			let customer = vendor.customer();
+
+			assert!(customer.latest().is_none());
+			vendor.send("first");
+			assert!(matches!(customer.latest(), Some("first")));
+
+			let mut request = Box::pin(customer.request());
+			assert!(future::poll_once(request.as_mut()).await.is_none());
+			assert!(vendor.has_waiters());
+
+			vendor.send("second");
+			assert!(matches!(request.await, Ok("second")));
+			assert!(matches!(customer.latest(), Some("second")));
+		});
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn cache_expired() {
+		smol::block_on(async move {
+			let vendor = Vendor::new().with_cache_max_age(Duration::from_millis(10));
+			let customer = vendor.customer();
+
+			vendor.send("ok");
+			assert!(matches!(customer.latest(), Some("ok")));
+			assert!(matches!(customer.request().await, Ok("ok")));
+			assert!(!vendor.has_waiters());
+
+			let mut fresh = Box::pin(customer.request_fresh());
+			assert!(future::poll_once(fresh.as_mut()).await.is_none());
+			drop(fresh);
+
+			smol::Timer::after(Duration::from_millis(20)).await;
+			assert!(customer.latest().is_none());
+
+			let mut request = Box::pin(customer.request());
+			assert!(future::poll_once(request.as_mut()).await.is_none());
+			assert!(vendor.has_waiters());
+		});
+	}
+
+	#[test]
+	fn wait_for_waiters() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let t1 = smol::spawn({
+				let vendor = vendor.clone();
+				async move {
+					assert!(vendor.wait_for_n(2).await);
+					vendor.send("ok");
+				}
+			});
+
+			let t2 = smol::spawn({
+				let customer = customer.clone();
+				async move { customer.request().await }
+			});
+
+			assert!(matches!(customer.request().await, Ok("ok")));
+			assert!(matches!(t2.await, Ok("ok")));
+			t1.await;
+
+			vendor.close();
+			assert!(!vendor.wait_for_waiters().await);
+		});
+	}
+
+	#[test]
+	fn serve_with() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+			let produced = Arc::new(AtomicUsize::new(0));
+
+			let t1 = smol::spawn({
+				let vendor = vendor.clone();
+				let produced = produced.clone();
+				async move {
+					vendor
+						.serve_with(|| {
+							let produced = produced.clone();
+							async move {
+								smol::Timer::after(Duration::from_millis(10)).await;
+								produced.fetch_add(1, Ordering::Relaxed)
+							}
+						})
+						.await;
+				}
+			});
+
+			let (first, second) = future::zip(customer.request(), customer.request()).await;
+			assert!(matches!((first, second), (Ok(0), Ok(0))));
+			assert_eq!(produced.load(Ordering::Relaxed), 1);
+
+			vendor.close();
+			t1.await;
+		});
+	}
+
+	#[test]
+	fn serve_with_unmatched() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+			let produced = AtomicUsize::new(0);
+
+			let odd = customer.request_where(|n| n % 2 == 1);
+			let serve = vendor.serve_with(|| {
+				let produced = &produced;
+				async move {
+					future::yield_now().await;
+					produced.fetch_add(1, Ordering::Relaxed) * 2
+				}
+			});
+
+			future::or(serve, async {
+				smol::Timer::after(Duration::from_millis(20)).await;
+			})
+			.await;
+
+			assert_eq!(produced.load(Ordering::Relaxed), 1);
+			assert_eq!(vendor.waiters_count(), 1);
+			drop(odd);
+		});
+	}
+
+	#[test]
+	fn single_flight() {
+		smol::block_on(async move {
+			let group = SingleFlight::new();
+			let computed = AtomicUsize::new(0);
+
+			let compute = |value| {
+				let computed = &computed;
+				move || async move {
+					computed.fetch_add(1, Ordering::Relaxed);
+					smol::Timer::after(Duration::from_millis(10)).await;
+					value
+				}
+			};
+
+			let (first, second) = future::zip(group.run("a", compute(1)), group.run("a", compute(2))).await;
+			assert!(matches!((first, second), (Ok(1), Ok(1))));
+			assert_eq!(computed.load(Ordering::Relaxed), 1);
+			assert!(!group.is_running(&"a"));
+
+			assert!(matches!(group.run("b", compute(3)).await, Ok(3)));
+			assert_eq!(computed.load(Ordering::Relaxed), 2);
+		});
+	}
+
+	#[test]
+	fn single_flight_cancelled() {
+		smol::block_on(async move {
+			let group = SingleFlight::new();
+
+			let mut leader = Box::pin(group.run("a", future::pending));
+			let mut follower = Box::pin(group.run("a", || future::ready(2)));
+			assert!(future::poll_once(leader.as_mut()).await.is_none());
+			assert!(future::poll_once(follower.as_mut()).await.is_none());
+
+			drop(leader);
+			assert!(matches!(follower.await, Ok(2)));
+		});
+	}
+
+	#[test]
+	fn topics() {
+		smol::block_on(async move {
+			let vendor = TopicVendor::new();
+			let customer = vendor.customer();
+
+			let mut front = Box::pin(customer.request("front"));
+			let mut back = Box::pin(customer.request("back"));
+			assert!(future::poll_once(front.as_mut()).await.is_none());
+			assert!(future::poll_once(back.as_mut()).await.is_none());
+			assert_eq!(vendor.topics_count(), 2);
+
+			assert_eq!(vendor.send(&"front", 1).delivered, 1);
+			assert!(vendor.send(&"side", 2).is_lost());
+			assert!(matches!(front.await, Ok(1)));
+			assert!(future::poll_once(back.as_mut()).await.is_none());
+			assert_eq!(vendor.topics_count(), 1);
+
+			drop(back);
+			assert_eq!(vendor.topics_count(), 0);
+
+			let mut back = Box::pin(customer.request("back"));
+			assert!(future::poll_once(back.as_mut()).await.is_none());
+			drop(vendor);
+			assert!(matches!(back.await, Err(RequestError::Closed)));
+			assert!(customer.is_closed());
+		});
+	}
+
+	#[test]
+	fn eager_topic_request() {
+		smol::block_on(async move {
+			let vendor = TopicVendor::new();
+			let customer = vendor.customer();
+
+			let front = customer.request("front");
+			assert!(vendor.has_waiters(&"front"));
+
+			vendor.send(&"front", 1);
+			assert!(matches!(front.await, Ok(1)));
+			assert_eq!(vendor.topics_count(), 0);
+
+			vendor.close();
+			assert!(matches!(customer.request("front").await, Err(RequestError::Closed)));
+		});
+	}
+
+	#[test]
+	fn request_with_priority() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let mut batch = Box::pin(customer.request_with_priority(-1));
+			let mut plain = Box::pin(customer.request());
+			let mut first = Box::pin(customer.request_with_priority(1));
+			let mut second = Box::pin(customer.request_with_priority(1));
+			assert!(future::poll_once(batch.as_mut()).await.is_none());
+			assert!(future::poll_once(plain.as_mut()).await.is_none());
+			assert!(future::poll_once(first.as_mut()).await.is_none());
+			assert!(future::poll_once(second.as_mut()).await.is_none());
+
+			for job in 1..=4 {
+				assert!(vendor.send_one(job).is_ok());
+			}
+
+			assert!(matches!(first.await, Ok(1)));
+			assert!(matches!(second.await, Ok(2)));
+			assert!(matches!(plain.await, Ok(3)));
+			assert!(matches!(batch.await, Ok(4)));
+		});
+	}
+
+	#[test]
+	fn bounded_evict_lowest_priority() {
+		smol::block_on(async move {
+			let vendor = Vendor::bounded(2, Overflow::EvictOldest);
+			let customer = vendor.customer();
+
+			let urgent = customer.request_with_priority(1);
+			let batch = customer.request_with_priority(-1);
+			let plain = customer.request();
+			assert!(matches!(batch.await, Err(RequestError::Evicted)));
+
+			vendor.send("ok");
+			assert!(matches!(urgent.await, Ok("ok")));
+			assert!(matches!(plain.await, Ok("ok")));
+		});
+	}
+
+	#[test]
+	fn bounded_evict_lower_newcomer() {
+		smol::block_on(async move {
+			let vendor = Vendor::bounded(1, Overflow::EvictOldest);
+			let customer = vendor.customer();
+
+			let urgent = customer.request_with_priority(10);
+			let batch = customer.request_with_priority(-10);
+			assert!(matches!(batch.await, Err(RequestError::Evicted)));
+			assert_eq!(vendor.waiters_count(), 1);
+
+			assert!(vendor.send_one("job").is_ok());
+			assert!(matches!(urgent.await, Ok("job")));
+		});
+	}
+
+	#[test]
+	fn send_error() {
+		smol::block_on(async move {
+			let vendor = Vendor::<()>::new();
+			let customer = vendor.customer();
+
+			let mut subscription = customer.subscribe();
+			vendor.send(());
+			assert!(subscription.error().is_none());
+
+			let mut first = Box::pin(customer.request());
+			let mut second = Box::pin(customer.request_where(|()| false));
+			assert!(future::poll_once(first.as_mut()).await.is_none());
+			assert!(future::poll_once(second.as_mut()).await.is_none());
+
+			let report = vendor.send_error(std::io::Error::other("camera disconnected"));
+			assert_eq!(report.delivered, 3);
+			assert!(!vendor.has_waiters());
+			assert!(matches!(first.await, Err(RequestError::Producer(_))));
+
+			let Err(RequestError::Producer(error)) = second.await else { panic!("error is not delivered") };
+			assert_eq!(error.to_string(), "camera disconnected");
+			assert!(!vendor.is_closed());
+
+			assert_eq!(subscription.next().await, Some(()));
+			assert_eq!(subscription.next().await, None);
+			assert!(matches!(subscription.error(), Some(RequestError::Producer(_))));
+		});
+	}
+
+	#[test]
+	fn enqueue() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let Ok(mut first) = customer.enqueue() else { panic!("failed to enqueue") };
+			let Ok(second) = customer.enqueue() else { panic!("failed to enqueue") };
+			assert_eq!(vendor.waiters_count(), 2);
+			assert!(!first.is_ready());
+			assert!(first.try_take().is_none());
+
+			vendor.send("ok");
+			assert!(first.is_ready());
+			assert!(matches!(first.try_take(), Some(Ok("ok"))));
+			assert!(first.try_take().is_none());
+			assert!(matches!(second.await, Ok("ok")));
+
+			drop(customer.enqueue());
+			assert!(!vendor.has_waiters());
+		});
+	}
+
+	#[test]
+	fn ticket_keeps_waker() {
+		struct Flag(AtomicBool);
+
+		impl Wake for Flag {
+			fn wake(self: Arc<Self>) {
+				self.0.store(true, Ordering::Relaxed);
+			}
+		}
+
+		let vendor = Vendor::new();
+		let customer = vendor.customer();
+		let flag = Arc::new(Flag(AtomicBool::new(false)));
+		let waker = Waker::from(flag.clone());
+
+		let Ok(mut ticket) = customer.enqueue() else { panic!("failed to enqueue") };
+		assert!(Pin::new(&mut ticket).poll(&mut Context::from_waker(&waker)).is_pending());
+		assert!(!ticket.is_ready());
+		assert!(ticket.try_take().is_none());
+
+		vendor.send("ok");
+		assert!(flag.0.load(Ordering::Relaxed));
+		assert!(matches!(ticket.try_take(), Some(Ok("ok"))));
+	}
+
+	#[test]
+	fn request_builder() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let Ok(odd) = customer.request_builder().filter(|n| n % 2 == 1).register() else { panic!("failed to register") };
+			let Ok(urgent) = customer.request_builder().priority(1).register() else { panic!("failed to register") };
+			assert_eq!(vendor.waiters_count(), 2);
+
+			assert!(vendor.send_one(2).is_ok());
+			assert!(matches!(urgent.await, Ok(2)));
+
+			vendor.send(3);
+			assert!(matches!(odd.await, Ok(3)));
+
+			let vendor = Vendor::<u32, u32>::queried();
+			let customer = vendor.customer();
+			let Ok(doubled) = customer.query_builder(21).register() else { panic!("failed to register") };
+			vendor.serve(|n| n * 2);
+			assert!(matches!(doubled.await, Ok(42)));
+		});
+	}
+
+	#[test]
+	fn enqueue_full() {
+		let vendor = Vendor::<()>::bounded(1, Overflow::Wait);
+		let customer = vendor.customer();
+
+		let first = customer.enqueue();
+		assert!(first.is_ok());
+		assert!(matches!(customer.enqueue(), Err(RequestError::Full)));
+	}
+
+	#[test]
+	fn eager_request() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let request = customer.request();
+			let owned = customer.clone().into_request();
+			let burst = customer.request_many(1);
+			assert_eq!(vendor.waiters_count(), 3);
+
+			vendor.send("ok");
+			assert!(matches!(request.await, Ok("ok")));
+			assert!(matches!(owned.await, Ok("ok")));
+			assert_eq!(burst.await.ok(), Some(vec!["ok"]));
+		});
+	}
+
+	#[test]
+	fn request_bounds() {
+		fn assert_send_unpin<F: Future + Send + Unpin>(_: &F) {}
+
+		let vendor = Vendor::<String>::new();
+		let customer = vendor.customer();
+
+		assert_send_unpin(&customer.request());
+		assert_send_unpin(&customer.request_where(String::is_empty));
+		assert_send_unpin(&customer.clone().into_request());
+		assert_send_unpin(&customer.request_many(2));
+
+		#[cfg(feature = "std")]
+		{
+			assert_send_unpin(&customer.request_timeout(Duration::from_secs(1)));
+			assert_send_unpin(&customer.request_window(Duration::from_secs(1)));
+		}
+	}
+
+	#[test]
+	fn fused_request() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let mut request = customer.request();
+			assert!(!request.is_terminated());
+
+			vendor.send("ok");
+			assert!(matches!((&mut request).await, Ok("ok")));
+			assert!(request.is_terminated());
+			assert!(future::poll_once(&mut request).await.is_none());
+		});
+	}
+
+	#[test]
+	fn cancelled_request() {
+		smol::block_on(async move {
+			let vendor = Vendor::<()>::new();
+			let customer = vendor.customer();
+
+			let mut request = Box::pin(customer.request());
+			assert!(future::poll_once(request.as_mut()).await.is_none());
+			assert_eq!(vendor.waiters_count(), 1);
+
+			drop(request);
+			assert!(!vendor.has_waiters());
+		});
+	}
+
+	#[test]
+	fn vendor_dropped() {
+		smol::block_on(async move {
+			let vendor = Vendor::<()>::new();
+			let customer = vendor.customer();
+
+			let t1 = smol::spawn({
+				let customer = customer.clone();
+				async move { customer.request().await }
+			});
+
+			while !vendor.has_waiters() {
+				future::yield_now().await;
+			}
+
+			drop(vendor.clone());
+			assert!(!customer.is_closed());
+
+			drop(vendor);
+			assert!(customer.is_closed());
+			assert!(matches!(t1.await, Err(RequestError::Closed)));
+			assert!(matches!(customer.request().await, Err(RequestError::Closed)));
+		});
+	}
+
+	#[test]
+	fn vendor_closed() {
+		smol::block_on(async move {
+			let vendor = Vendor::<()>::new();
+			let customer = vendor.customer();
+
+			let t1 = smol::spawn({
+				let customer = customer.clone();
+				async move { customer.request().await }
+			});
+
+			while !vendor.has_waiters() {
+				future::yield_now().await;
+			}
+
+			assert!(vendor.close());
+			assert!(!vendor.close());
+			assert!(vendor.is_closed());
+			assert!(matches!(t1.await, Err(RequestError::Closed)));
+			assert!(matches!(customer.request().await, Err(RequestError::Closed)));
+		});
+	}
+
+	#[test]
+	fn bounded_reject() {
+		smol::block_on(async move {
+			let vendor = Vendor::<()>::bounded(1, Overflow::Reject);
+			let customer = vendor.customer();
+
+			let mut first = Box::pin(customer.request());
+			assert!(future::poll_once(first.as_mut()).await.is_none());
+			assert!(matches!(customer.request().await, Err(RequestError::Full)));
+		});
+	}
+
+	#[test]
+	fn bounded_evict_oldest() {
+		smol::block_on(async move {
+			let vendor = Vendor::bounded(1, Overflow::EvictOldest);
+			let customer = vendor.customer();
+
+			let mut first = Box::pin(customer.request());
+			assert!(future::poll_once(first.as_mut()).await.is_none());
+
+			let mut second = Box::pin(customer.request());
+			assert!(future::poll_once(second.as_mut()).await.is_none());
+			assert_eq!(vendor.waiters_count(), 1);
+
+			vendor.send("ok");
+			assert!(matches!(first.await, Err(RequestError::Evicted)));
+			assert!(matches!(second.await, Ok("ok")));
+		});
+	}
+
+	#[test]
+	fn bounded_wait() {
+		smol::block_on(async move {
+			let vendor = Vendor::bounded(1, Overflow::Wait);
+			let customer = vendor.customer();
+
+			let mut first = Box::pin(customer.request());
+			assert!(future::poll_once(first.as_mut()).await.is_none());
+
+			let mut second = Box::pin(customer.request());
+			assert!(future::poll_once(second.as_mut()).await.is_none());
+			assert_eq!(vendor.waiters_count(), 1);
+
+			vendor.send("first");
+			assert!(matches!(first.await, Ok("first")));
+			assert!(future::poll_once(second.as_mut()).await.is_none());
+			assert_eq!(vendor.waiters_count(), 1);
+
+			vendor.send("second");
+			assert!(matches!(second.await, Ok("second")));
+		});
+	}
+
+	#[test]
+	fn bounded_wait_many() {
+		smol::block_on(async move {
+			let vendor = Vendor::bounded(2, Overflow::Wait);
+			let customer = vendor.customer();
+
+			let first = customer.request();
+			let second = customer.request();
+			let mut third = customer.request();
+			let mut fourth = customer.request();
+			assert!(future::poll_once(&mut third).await.is_none());
+			assert!(future::poll_once(&mut fourth).await.is_none());
+
+			assert!(vendor.send_one("first").is_ok());
+			assert!(vendor.send_one("second").is_ok());
+			assert!(future::poll_once(&mut third).await.is_none());
+			assert!(future::poll_once(&mut fourth).await.is_none());
+			assert_eq!(vendor.waiters_count(), 2);
+
+			assert!(matches!(first.await, Ok("first")));
+			assert!(matches!(second.await, Ok("second")));
+		});
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn request_timeout() {
+		smol::block_on(async move {
+			let vendor = Vendor::<()>::new();
+			let customer = vendor.customer();
+
+			let result = customer.request_timeout(Duration::from_millis(10)).await;
+			assert!(matches!(result, Err(RequestError::Timeout)));
+		});
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn request_until_sent() {
+		smol::block_on(async move {
+			let vendor = Vendor::new();
+			let customer = vendor.customer();
+
+			let t1 = smol::spawn(async move {
+				let deadline = Instant::now() + Duration::from_secs(5);
+				assert!(matches!(customer.request_until(deadline).await, Ok("ok")));
+			});
+
+			while !vendor.has_waiters() {
+				future::yield_now().await;
+			}
+
+			vendor.send("ok");
+			t1.await;
+		});
+	}
 }
@@ -0,0 +1,132 @@
+use alloc::sync::Arc;
+use core::{
+	fmt,
+	task::{Context, Poll, Waker},
+};
+
+use crate::sync::Mutex;
+
+/// Slot shared by the two halves of a oneshot channel,
+/// waking the receiver through its [Waker] so it works under any executor
+struct Slot<T> {
+	state: Mutex<State<T>>,
+}
+
+struct State<T> {
+	value: Option<T>,
+	waker: Option<Waker>,
+	sender: bool,
+	receiver: bool,
+}
+
+/// The [Sender] was dropped without sending
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RecvError;
+
+pub(crate) fn channel<T>() -> (Sender<T>, Receiver<T>) {
+	let slot = Arc::new(Slot { state: Mutex::new(State { value: None, waker: None, sender: true, receiver: true }) });
+
+	(Sender { slot: slot.clone() }, Receiver { slot })
+}
+
+pub(crate) struct Sender<T> {
+	slot: Arc<Slot<T>>,
+}
+
+impl<T> Sender<T> {
+	/// Puts the value into the slot and wakes the receiver
+	///
+	/// # Errors
+	/// Returns the value back if the [Receiver] has been dropped
+	pub(crate) fn send(self, value: T) -> Result<(), T> {
+		let mut state = self.slot.state.lock();
+
+		if !state.receiver {
+			return Err(value);
+		}
+
+		state.value = Some(value);
+		let waker = state.waker.take();
+		drop(state);
+
+		if let Some(waker) = waker {
+			waker.wake();
+		}
+
+		Ok(())
+	}
+}
+
+impl<T> Drop for Sender<T> {
+	fn drop(&mut self) {
+		let mut state = self.slot.state.lock();
+		state.sender = false;
+		let waker = state.waker.take();
+		drop(state);
+
+		if let Some(waker) = waker {
+			waker.wake();
+		}
+	}
+}
+
+impl<T> fmt::Debug for Sender<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Sender").finish_non_exhaustive()
+	}
+}
+
+pub(crate) struct Receiver<T> {
+	slot: Arc<Slot<T>>,
+}
+
+impl<T> Receiver<T> {
+	/// Takes the sent value, or registers the waker to be woken once
+	/// the value is sent or the [Sender] is dropped
+	pub(crate) fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<T, RecvError>> {
+		let mut state = self.slot.state.lock();
+
+		if let Some(value) = state.value.take() {
+			return Poll::Ready(Ok(value));
+		}
+
+		if !state.sender {
+			return Poll::Ready(Err(RecvError));
+		}
+
+		match &mut state.waker {
+			Some(waker) => waker.clone_from(cx.waker()),
+			None => state.waker = Some(cx.waker().clone()),
+		}
+
+		Poll::Pending
+	}
+
+	/// Takes the sent value without registering a waker, so the one left by
+	/// the last [`Receiver::poll_recv`] is still woken.
+	///
+	/// Returns `None` while the value is not sent yet
+	pub(crate) fn try_recv(&mut self) -> Option<Result<T, RecvError>> {
+		let mut state = self.slot.state.lock();
+
+		if let Some(value) = state.value.take() {
+			return Some(Ok(value));
+		}
+
+		(!state.sender).then_some(Err(RecvError))
+	}
+}
+
+impl<T> Drop for Receiver<T> {
+	fn drop(&mut self) {
+		let mut state = self.slot.state.lock();
+		state.receiver = false;
+		state.waker = None;
+	}
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Receiver").finish_non_exhaustive()
+	}
+}
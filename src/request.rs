@@ -0,0 +1,253 @@
+use alloc::boxed::Box;
+use core::{
+	fmt,
+	future::Future,
+	mem,
+	pin::Pin,
+	task::{Context, Poll},
+};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use event_listener::EventListener;
+use futures_core::FusedFuture;
+
+#[cfg(feature = "std")]
+use crate::timer::{self, Timeout};
+use crate::{
+	Customer, RequestError, Ticket,
+	oneshot::{Receiver, channel},
+	waiters::{Admission, Filter, Waiter},
+};
+
+type Response<T> = Result<T, RequestError>;
+
+/// Builder of a request, see [`Customer::request_builder`]
+///
+/// The request takes its place in the queue only when
+/// [registered](RequestBuilder::register), so it can be configured first
+#[must_use = "the request is not queued until registered"]
+pub struct RequestBuilder<'a, T, Q = ()> {
+	customer: &'a Customer<T, Q>,
+	query: Q,
+	priority: i32,
+	filter: Option<Filter<T>>,
+}
+
+impl<'a, T, Q> RequestBuilder<'a, T, Q> {
+	pub(crate) fn new(customer: &'a Customer<T, Q>, query: Q) -> Self {
+		Self { customer, query, priority: 0, filter: None }
+	}
+
+	/// Sets the priority of the request, see
+	/// [`Customer::request_with_priority`]
+	pub fn priority(mut self, priority: i32) -> Self {
+		self.priority = priority;
+		self
+	}
+
+	/// Only accepts resources satisfying the predicate, see
+	/// [`Customer::request_where`]
+	pub fn filter<F>(mut self, predicate: F) -> Self
+	where
+		F: Fn(&T) -> bool + Send + 'static,
+	{
+		self.filter = Some(Box::new(predicate));
+		self
+	}
+
+	/// Puts the request into the queue right away, so the
+	/// [Vendor](crate::Vendor) sees it before the returned [Ticket] is polled
+	///
+	/// # Errors
+	/// Same as [`Customer::enqueue`]
+	pub fn register(self) -> Result<Ticket<T, Q>, RequestError> {
+		let (tx, rx) = channel();
+		let waiter = Waiter::new(self.query, self.priority, tx, self.filter);
+		let registration = self.customer.waiters.try_register(waiter)?;
+
+		Ok(Ticket::new(rx, registration))
+	}
+}
+
+impl<T, Q: fmt::Debug> fmt::Debug for RequestBuilder<'_, T, Q> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("RequestBuilder")
+			.field("query", &self.query)
+			.field("priority", &self.priority)
+			.field("filtered", &self.filter.is_some())
+			.finish_non_exhaustive()
+	}
+}
+
+/// Future of a request, see [`Customer::request`]
+///
+/// The request takes its place in the queue as soon as the future is
+/// created, so the [Vendor](crate::Vendor) sees it before the first poll.
+/// Dropping the future leaves the queue.
+///
+/// The future is [Unpin], so it can be polled without boxing,
+/// and [Send] if both the resource and the query are [Send]
+#[must_use = "dropping the request leaves the queue"]
+pub struct RequestFuture<'a, T, Q = ()> {
+	customer: &'a Customer<T, Q>,
+	state: State<T, Q>,
+}
+
+/// Same as [`RequestFuture`], but owning its [Customer], so it can outlive
+/// the customer it was created from, see [`Customer::into_request`]
+#[must_use = "dropping the request leaves the queue"]
+pub struct OwnedRequestFuture<T, Q = ()> {
+	customer: Customer<T, Q>,
+	state: State<T, Q>,
+}
+
+enum State<T, Q> {
+	/// Resolved without waiting, e.g. from the cache
+	Ready(Result<T, RequestError>),
+	/// Waiting for room in a queue bounded with
+	/// [`Overflow::Wait`](crate::Overflow::Wait)
+	Room(Waiter<T, Q>, Receiver<Response<T>>, EventListener),
+	Queued(Ticket<T, Q>),
+	Done,
+}
+
+impl<T, Q> State<T, Q> {
+	fn new(customer: &Customer<T, Q>, query: Q, priority: i32, filter: Option<Filter<T>>, cached: bool) -> Self {
+		if let Some(resource) = cached.then(|| customer.waiters.cached_fresh()).flatten() {
+			return Self::Ready(Ok(resource));
+		}
+
+		let (tx, rx) = channel();
+		Self::admit(customer, Waiter::new(query, priority, tx, filter), rx)
+	}
+
+	fn admit(customer: &Customer<T, Q>, waiter: Waiter<T, Q>, rx: Receiver<Response<T>>) -> Self {
+		match customer.waiters.admit(waiter) {
+			Ok(Admission::Admitted(registration)) => Self::Queued(Ticket::new(rx, registration)),
+			Ok(Admission::Wait(waiter, listener)) => Self::Room(waiter, rx, listener),
+			Err(err) => Self::Ready(Err(err)),
+		}
+	}
+
+	fn poll(&mut self, customer: &Customer<T, Q>, cx: &mut Context<'_>) -> Poll<Result<T, RequestError>> {
+		loop {
+			match mem::replace(self, Self::Done) {
+				Self::Ready(result) => return Poll::Ready(result),
+				Self::Room(waiter, rx, mut listener) => {
+					if Pin::new(&mut listener).poll(cx).is_pending() {
+						*self = Self::Room(waiter, rx, listener);
+						return Poll::Pending;
+					}
+
+					*self = Self::admit(customer, waiter, rx);
+				}
+				Self::Queued(mut ticket) => {
+					let Poll::Ready(result) = Pin::new(&mut ticket).poll(cx) else {
+						*self = Self::Queued(ticket);
+						return Poll::Pending;
+					};
+
+					return Poll::Ready(result);
+				}
+				Self::Done => return Poll::Pending,
+			}
+		}
+	}
+}
+
+impl<'a, T, Q> RequestFuture<'a, T, Q> {
+	pub(crate) fn new(
+		customer: &'a Customer<T, Q>,
+		query: Q,
+		priority: i32,
+		filter: Option<Filter<T>>,
+		cached: bool,
+	) -> Self {
+		Self { customer, state: State::new(customer, query, priority, filter, cached) }
+	}
+}
+
+impl<T, Q> OwnedRequestFuture<T, Q> {
+	pub(crate) fn new(customer: Customer<T, Q>, query: Q, cached: bool) -> Self {
+		let state = State::new(&customer, query, 0, None, cached);
+		Self { customer, state }
+	}
+}
+
+/// Same as [`RequestFuture`], but failing with [`RequestError::Timeout`]
+/// once the deadline is reached, see [`Customer::request_timeout`]
+#[cfg(feature = "std")]
+#[must_use = "dropping the request leaves the queue"]
+pub struct TimeoutFuture<'a, T> {
+	request: Timeout<RequestFuture<'a, T>>,
+}
+
+#[cfg(feature = "std")]
+impl<'a, T> TimeoutFuture<'a, T> {
+	pub(crate) fn new(request: RequestFuture<'a, T>, deadline: Option<Instant>) -> Self {
+		Self { request: timer::timeout(deadline, request) }
+	}
+}
+
+// The resource is never pinned, only moved out of the future
+impl<T, Q> Unpin for RequestFuture<'_, T, Q> {}
+impl<T, Q> Unpin for OwnedRequestFuture<T, Q> {}
+
+impl<T, Q> Future for RequestFuture<'_, T, Q> {
+	type Output = Result<T, RequestError>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let this = &mut *self;
+		this.state.poll(this.customer, cx)
+	}
+}
+
+impl<T, Q> Future for OwnedRequestFuture<T, Q> {
+	type Output = Result<T, RequestError>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let this = &mut *self;
+		this.state.poll(&this.customer, cx)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T> Future for TimeoutFuture<'_, T> {
+	type Output = Result<T, RequestError>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		Pin::new(&mut self.request).poll(cx).map(|result| result.unwrap_or(Err(RequestError::Timeout)))
+	}
+}
+
+impl<T, Q> FusedFuture for RequestFuture<'_, T, Q> {
+	fn is_terminated(&self) -> bool {
+		matches!(self.state, State::Done)
+	}
+}
+
+impl<T, Q> FusedFuture for OwnedRequestFuture<T, Q> {
+	fn is_terminated(&self) -> bool {
+		matches!(self.state, State::Done)
+	}
+}
+
+impl<T, Q> fmt::Debug for RequestFuture<'_, T, Q> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("RequestFuture").field("terminated", &self.is_terminated()).finish_non_exhaustive()
+	}
+}
+
+impl<T, Q> fmt::Debug for OwnedRequestFuture<T, Q> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("OwnedRequestFuture").field("terminated", &self.is_terminated()).finish_non_exhaustive()
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T> fmt::Debug for TimeoutFuture<'_, T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("TimeoutFuture").finish_non_exhaustive()
+	}
+}
@@ -0,0 +1,120 @@
+use alloc::collections::BTreeMap;
+use core::future::Future;
+
+use crate::{
+	RequestError, Vendor,
+	sync::{Mutex, MutexGuard},
+};
+
+/// Deduplicates concurrent computations of the same key: while a computation
+/// is in flight, further requests for its key wait for the result
+/// instead of starting their own
+#[derive(Debug)]
+pub struct SingleFlight<K, T> {
+	flights: Mutex<BTreeMap<K, Vendor<T>>>,
+}
+
+impl<K, T> Default for SingleFlight<K, T> {
+	fn default() -> Self {
+		Self { flights: Mutex::new(BTreeMap::new()) }
+	}
+}
+
+impl<K, T> SingleFlight<K, T>
+where
+	K: Ord + Clone,
+	T: Clone,
+{
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	fn lock(&self) -> MutexGuard<'_, BTreeMap<K, Vendor<T>>> {
+		self.flights.lock()
+	}
+
+	/// Runs `compute` unless a computation for the key is already in flight,
+	/// in which case its result is awaited instead.
+	///
+	/// If the running computation is cancelled, one of the waiting
+	/// requests takes over with its own `compute`
+	///
+	/// # Errors
+	/// Same as [`Customer::request`](crate::Customer::request)
+	pub async fn run<F, Fut>(&self, key: K, compute: F) -> Result<T, RequestError>
+	where
+		F: FnOnce() -> Fut,
+		Fut: Future<Output = T>,
+	{
+		loop {
+			let customer = {
+				let mut flights = self.lock();
+
+				let Some(vendor) = flights.get(&key) else {
+					flights.insert(key.clone(), Vendor::new().with_cache());
+					break;
+				};
+
+				vendor.customer()
+			};
+
+			// The result is cached before the flight closes,
+			// so being closed without it means the flight was cancelled
+			match customer.request().await {
+				Err(RequestError::Closed) => {
+					if let Some(resource) = customer.latest() {
+						return Ok(resource);
+					}
+				}
+				result => return result,
+			}
+		}
+
+		let flight = Flight { group: self, key: Some(key) };
+		let resource = compute().await;
+
+		if let Some(vendor) = flight.land() {
+			vendor.send(resource.clone());
+		}
+
+		Ok(resource)
+	}
+
+	/// Returns `true` if a computation for the key is in flight
+	pub fn is_running(&self, key: &K) -> bool {
+		self.lock().contains_key(key)
+	}
+}
+
+/// Removes the flight from the group once it lands or gets cancelled
+struct Flight<'a, K, T>
+where
+	K: Ord + Clone,
+	T: Clone,
+{
+	group: &'a SingleFlight<K, T>,
+	key: Option<K>,
+}
+
+impl<K, T> Flight<'_, K, T>
+where
+	K: Ord + Clone,
+	T: Clone,
+{
+	fn land(mut self) -> Option<Vendor<T>> {
+		let key = self.key.take()?;
+		self.group.lock().remove(&key)
+	}
+}
+
+impl<K, T> Drop for Flight<'_, K, T>
+where
+	K: Ord + Clone,
+	T: Clone,
+{
+	fn drop(&mut self) {
+		if let Some(key) = self.key.take() {
+			self.group.lock().remove(&key);
+		}
+	}
+}
@@ -0,0 +1,184 @@
+use alloc::{collections::VecDeque, sync::Arc};
+use core::{
+	pin::Pin,
+	task::{Context, Poll, Waker},
+};
+
+use futures_core::Stream;
+
+use crate::{
+	RequestError,
+	sync::{Mutex, MutexGuard},
+	waiters::Waiters,
+};
+
+/// How many resources a [Subscription] keeps while nobody polls it.
+/// A capacity of zero is treated as one.
+///
+/// Defaults to keeping only the latest resource, i.e. `DropOldest(1)`,
+/// so a slow subscriber does not make the memory grow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Buffer {
+	/// Every resource is kept until it is taken, so the buffer grows
+	/// without limit if the subscription is polled slower than resources
+	/// are sent
+	Unbounded,
+	/// At most the given number of resources is kept,
+	/// the oldest one is discarded to make room
+	DropOldest(usize),
+	/// At most the given number of resources is kept,
+	/// new resources are discarded while the buffer is full
+	DropNewest(usize),
+}
+
+impl Default for Buffer {
+	fn default() -> Self {
+		Self::DropOldest(1)
+	}
+}
+
+/// Resources sent to a single [Subscription], waiting to be taken
+#[derive(Debug)]
+pub(crate) struct Feed<T> {
+	state: Mutex<FeedState<T>>,
+	buffer: Buffer,
+}
+
+#[derive(Debug)]
+struct FeedState<T> {
+	resources: VecDeque<T>,
+	waker: Option<Waker>,
+	/// Why the feed ended, if it did
+	end: Option<RequestError>,
+}
+
+impl<T> Feed<T> {
+	pub(crate) fn new(buffer: Buffer) -> Self {
+		Self { state: Mutex::new(FeedState { resources: VecDeque::new(), waker: None, end: None }), buffer }
+	}
+
+	fn lock(&self) -> MutexGuard<'_, FeedState<T>> {
+		self.state.lock()
+	}
+
+	/// Buffers the resource according to the buffering policy.
+	/// Returns `false` if the resource was discarded
+	pub(crate) fn push(&self, resource: T) -> bool {
+		let mut state = self.lock();
+
+		match self.buffer {
+			Buffer::Unbounded => {}
+			Buffer::DropOldest(capacity) => {
+				while state.resources.len() >= capacity.max(1) {
+					state.resources.pop_front();
+				}
+			}
+			Buffer::DropNewest(capacity) => {
+				if state.resources.len() >= capacity.max(1) {
+					return false;
+				}
+			}
+		}
+
+		state.resources.push_back(resource);
+		let waker = state.waker.take();
+		drop(state);
+
+		if let Some(waker) = waker {
+			waker.wake();
+		}
+
+		true
+	}
+
+	/// Ends the subscription once the buffered resources are taken
+	pub(crate) fn close(&self) {
+		self.end(RequestError::Closed);
+	}
+
+	/// Same as [`Feed::close`], but with the given reason,
+	/// unless the subscription has already ended
+	pub(crate) fn end(&self, reason: RequestError) {
+		let mut state = self.lock();
+		state.end.get_or_insert(reason);
+		let waker = state.waker.take();
+		drop(state);
+
+		if let Some(waker) = waker {
+			waker.wake();
+		}
+	}
+}
+
+/// Stream of every resource sent by the linked [Vendor](crate::Vendor)
+/// since subscribing, see [`Customer::subscribe`](crate::Customer::subscribe)
+///
+/// The stream ends once the vendors are closed or gone, or once
+/// [`Vendor::send_error`](crate::Vendor::send_error) is called,
+/// see [`Subscription::error`].
+/// Dropping the subscription leaves the queue
+#[derive(Debug)]
+pub struct Subscription<T, Q = ()> {
+	waiters: Arc<Waiters<T, Q>>,
+	feed: Arc<Feed<T>>,
+	key: Option<u64>,
+}
+
+impl<T, Q> Subscription<T, Q> {
+	pub(crate) fn new(waiters: Arc<Waiters<T, Q>>, buffer: Buffer) -> Self {
+		let feed = Arc::new(Feed::new(buffer));
+		let key = waiters.subscribe(feed.clone());
+
+		if key.is_none() {
+			feed.close();
+		}
+
+		Self { waiters, feed, key }
+	}
+
+	/// Returns why the stream ends once the buffered resources are taken:
+	/// [`RequestError::Closed`] if the vendors are closed or gone,
+	/// or [`RequestError::Producer`] if the vendor failed to produce.
+	///
+	/// Returns `None` while the subscription is live
+	pub fn error(&self) -> Option<RequestError> {
+		self.feed.lock().end.clone()
+	}
+
+	/// Takes the next resource, or the reason the stream ended,
+	/// see [`Subscription::error`]
+	pub(crate) fn poll_resource(&self, cx: &mut Context<'_>) -> Poll<Result<T, RequestError>> {
+		let mut state = self.feed.lock();
+
+		if let Some(resource) = state.resources.pop_front() {
+			return Poll::Ready(Ok(resource));
+		}
+
+		if let Some(end) = &state.end {
+			return Poll::Ready(Err(end.clone()));
+		}
+
+		match &mut state.waker {
+			Some(waker) => waker.clone_from(cx.waker()),
+			None => state.waker = Some(cx.waker().clone()),
+		}
+
+		Poll::Pending
+	}
+}
+
+impl<T, Q> Stream for Subscription<T, Q> {
+	type Item = T;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.poll_resource(cx).map(Result::ok)
+	}
+}
+
+impl<T, Q> Drop for Subscription<T, Q> {
+	fn drop(&mut self) {
+		if let Some(key) = self.key {
+			self.waiters.unsubscribe(key);
+		}
+	}
+}
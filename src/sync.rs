@@ -0,0 +1,192 @@
+//! Synchronization primitives, swapped for [loom](https://docs.rs/loom)'s
+//! model-checked counterparts when built with `--cfg ticque_loom`.
+//!
+//! Without the `std` feature the lock is either backed by the platform's
+//! [critical section](https://docs.rs/critical-section) with the
+//! `critical-section` feature, or a spin lock otherwise
+
+#[cfg(not(ticque_loom))]
+pub(crate) use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(ticque_loom)]
+pub(crate) use loom::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(all(not(any(ticque_loom, feature = "std")), feature = "critical-section"))]
+pub(crate) use self::critical::{Mutex, MutexGuard};
+#[cfg(any(ticque_loom, feature = "std"))]
+pub(crate) use self::poison::{Mutex, MutexGuard};
+#[cfg(not(any(ticque_loom, feature = "std", feature = "critical-section")))]
+pub(crate) use self::spin::{Mutex, MutexGuard};
+
+/// Mutex ignoring poisoning, as the guarded state stays consistent
+/// even if a panic happens while it is locked
+#[cfg(any(ticque_loom, feature = "std"))]
+mod poison {
+	#[cfg(not(ticque_loom))]
+	use std::sync as imp;
+	use std::sync::PoisonError;
+
+	#[cfg(ticque_loom)]
+	use loom::sync as imp;
+
+	pub(crate) type MutexGuard<'a, T> = imp::MutexGuard<'a, T>;
+
+	#[derive(Debug)]
+	pub(crate) struct Mutex<T>(imp::Mutex<T>);
+
+	impl<T> Mutex<T> {
+		pub(crate) fn new(value: T) -> Self {
+			Self(imp::Mutex::new(value))
+		}
+
+		pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+			self.0.lock().unwrap_or_else(PoisonError::into_inner)
+		}
+	}
+}
+
+/// Mutex entering a critical section for as long as it is locked,
+/// so it is safe to use from interrupt handlers on embedded targets
+#[cfg(all(not(any(ticque_loom, feature = "std")), feature = "critical-section"))]
+mod critical {
+	use core::{
+		cell::{Cell, UnsafeCell},
+		fmt,
+		ops::{Deref, DerefMut},
+	};
+
+	use critical_section::RestoreState;
+
+	pub(crate) struct Mutex<T> {
+		locked: Cell<bool>,
+		value: UnsafeCell<T>,
+	}
+
+	// Safety: the state is only accessed within a critical section,
+	// which is entered by a single holder at a time
+	unsafe impl<T: Send> Send for Mutex<T> {}
+	unsafe impl<T: Send> Sync for Mutex<T> {}
+
+	impl<T> Mutex<T> {
+		pub(crate) fn new(value: T) -> Self {
+			Self { locked: Cell::new(false), value: UnsafeCell::new(value) }
+		}
+
+		/// # Panics
+		/// If the mutex is already locked by the current holder of the
+		/// critical section, as critical sections are re-entrant
+		pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+			// Safety: released by the guard, guards are dropped in reverse order
+			let restore = unsafe { critical_section::acquire() };
+
+			if self.locked.replace(true) {
+				// Safety: acquired above
+				unsafe { critical_section::release(restore) };
+				panic!("mutex is locked re-entrantly");
+			}
+
+			MutexGuard { mutex: self, restore }
+		}
+	}
+
+	impl<T> fmt::Debug for Mutex<T> {
+		fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+			f.debug_struct("Mutex").finish_non_exhaustive()
+		}
+	}
+
+	pub(crate) struct MutexGuard<'a, T> {
+		mutex: &'a Mutex<T>,
+		restore: RestoreState,
+	}
+
+	impl<T> Deref for MutexGuard<'_, T> {
+		type Target = T;
+
+		fn deref(&self) -> &T {
+			// Safety: the lock is held by this guard
+			unsafe { &*self.mutex.value.get() }
+		}
+	}
+
+	impl<T> DerefMut for MutexGuard<'_, T> {
+		fn deref_mut(&mut self) -> &mut T {
+			// Safety: the lock is held by this guard
+			unsafe { &mut *self.mutex.value.get() }
+		}
+	}
+
+	impl<T> Drop for MutexGuard<'_, T> {
+		fn drop(&mut self) {
+			self.mutex.locked.set(false);
+			// Safety: acquired when the guard was created
+			unsafe { critical_section::release(self.restore) };
+		}
+	}
+}
+
+#[cfg(not(any(ticque_loom, feature = "std", feature = "critical-section")))]
+mod spin {
+	use core::{
+		cell::UnsafeCell,
+		fmt, hint,
+		ops::{Deref, DerefMut},
+		sync::atomic::{AtomicBool, Ordering},
+	};
+
+	pub(crate) struct Mutex<T> {
+		locked: AtomicBool,
+		value: UnsafeCell<T>,
+	}
+
+	// Safety: the value is only accessed through the guard,
+	// which is handed out to a single holder at a time
+	unsafe impl<T: Send> Send for Mutex<T> {}
+	unsafe impl<T: Send> Sync for Mutex<T> {}
+
+	impl<T> Mutex<T> {
+		pub(crate) fn new(value: T) -> Self {
+			Self { locked: AtomicBool::new(false), value: UnsafeCell::new(value) }
+		}
+
+		pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+			while self.locked.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+				hint::spin_loop();
+			}
+
+			MutexGuard { mutex: self }
+		}
+	}
+
+	impl<T> fmt::Debug for Mutex<T> {
+		fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+			f.debug_struct("Mutex").finish_non_exhaustive()
+		}
+	}
+
+	pub(crate) struct MutexGuard<'a, T> {
+		mutex: &'a Mutex<T>,
+	}
+
+	impl<T> Deref for MutexGuard<'_, T> {
+		type Target = T;
+
+		fn deref(&self) -> &T {
+			// Safety: the lock is held by this guard
+			unsafe { &*self.mutex.value.get() }
+		}
+	}
+
+	impl<T> DerefMut for MutexGuard<'_, T> {
+		fn deref_mut(&mut self) -> &mut T {
+			// Safety: the lock is held by this guard
+			unsafe { &mut *self.mutex.value.get() }
+		}
+	}
+
+	impl<T> Drop for MutexGuard<'_, T> {
+		fn drop(&mut self) {
+			self.mutex.locked.store(false, Ordering::Release);
+		}
+	}
+}
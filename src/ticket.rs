@@ -0,0 +1,81 @@
+use core::{
+	future::Future,
+	pin::Pin,
+	task::{Context, Poll},
+};
+
+use crate::{
+	RequestError,
+	oneshot::{Receiver, RecvError},
+	waiters::Registration,
+};
+
+/// Place in the queue of a [Vendor](crate::Vendor), reserved right away by
+/// [`Customer::enqueue`](crate::Customer::enqueue)
+///
+/// The ticket can be checked without blocking or awaited for the resource.
+/// Dropping the ticket leaves the queue
+#[derive(Debug)]
+#[must_use = "dropping the ticket leaves the queue"]
+pub struct Ticket<T, Q = ()> {
+	recv: Option<Receiver<Result<T, RequestError>>>,
+	ready: Option<Result<T, RequestError>>,
+	_registration: Registration<T, Q>,
+}
+
+impl<T, Q> Ticket<T, Q> {
+	pub(crate) fn new(recv: Receiver<Result<T, RequestError>>, registration: Registration<T, Q>) -> Self {
+		Self { recv: Some(recv), ready: None, _registration: registration }
+	}
+
+	/// Takes the resource if it has been sent already
+	///
+	/// Returns `None` if the resource is not sent yet
+	/// or has already been taken
+	pub fn try_take(&mut self) -> Option<Result<T, RequestError>> {
+		if let Some(result) = self.ready.take() {
+			return Some(result);
+		}
+
+		let result = self.recv.as_mut()?.try_recv()?;
+		self.recv = None;
+
+		Some(flatten(result))
+	}
+
+	/// Returns `true` if the resource can be taken without waiting
+	pub fn is_ready(&mut self) -> bool {
+		if self.ready.is_none() {
+			self.ready = self.try_take();
+		}
+
+		self.ready.is_some()
+	}
+
+	fn poll_resource(&mut self, cx: &mut Context<'_>) -> Poll<Result<T, RequestError>> {
+		if let Some(result) = self.ready.take() {
+			return Poll::Ready(result);
+		}
+
+		let Some(recv) = &mut self.recv else { return Poll::Pending };
+		let Poll::Ready(result) = recv.poll_recv(cx) else { return Poll::Pending };
+		self.recv = None;
+
+		Poll::Ready(flatten(result))
+	}
+}
+
+fn flatten<T>(result: Result<Result<T, RequestError>, RecvError>) -> Result<T, RequestError> {
+	result.map_err(RequestError::from).and_then(|result| result)
+}
+
+// The resource is never pinned, only moved out of the ticket
+impl<T, Q> Unpin for Ticket<T, Q> {}
+
+impl<T, Q> Future for Ticket<T, Q> {
+	type Output = Result<T, RequestError>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		self.poll_resource(cx)
+	}
+}
@@ -0,0 +1,181 @@
+use std::{
+	collections::BTreeMap,
+	future::Future,
+	mem,
+	pin::Pin,
+	sync::{Condvar, Mutex, MutexGuard, OnceLock, PoisonError},
+	task::{Context, Poll, Waker},
+	thread,
+	time::Instant,
+};
+
+type Key = (Instant, u64);
+
+/// Registry of pending deadlines driven by a single background thread,
+/// so timeouts work the same way under any executor
+#[derive(Debug, Default)]
+struct Timers {
+	entries: Mutex<Entries>,
+	wakeup: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+	next_id: u64,
+	wakers: BTreeMap<Key, Waker>,
+}
+
+impl Timers {
+	fn get() -> &'static Self {
+		static TIMERS: OnceLock<Timers> = OnceLock::new();
+
+		TIMERS.get_or_init(|| {
+			thread::spawn(|| Self::get().run());
+			Self::default()
+		})
+	}
+
+	fn lock(&self) -> MutexGuard<'_, Entries> {
+		self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+
+	fn run(&self) {
+		let mut entries = self.lock();
+
+		loop {
+			let now = Instant::now();
+			let pending = entries.wakers.split_off(&(now, u64::MAX));
+			let due = mem::replace(&mut entries.wakers, pending);
+
+			if !due.is_empty() {
+				drop(entries);
+				due.into_values().for_each(Waker::wake);
+				entries = self.lock();
+				continue;
+			}
+
+			entries = match entries.wakers.keys().next() {
+				Some(&(deadline, _)) => {
+					self.wakeup.wait_timeout(entries, deadline - now).unwrap_or_else(PoisonError::into_inner).0
+				}
+				None => self.wakeup.wait(entries).unwrap_or_else(PoisonError::into_inner),
+			};
+		}
+	}
+
+	fn register(&self, key: &mut Option<Key>, deadline: Instant, waker: &Waker) {
+		let mut entries = self.lock();
+
+		if let Some(current) = key.and_then(|key| entries.wakers.get_mut(&key)) {
+			if !current.will_wake(waker) {
+				current.clone_from(waker);
+			}
+
+			return;
+		}
+
+		let id = entries.next_id;
+		entries.next_id += 1;
+
+		let is_first = entries.wakers.keys().next().is_none_or(|&(first, _)| deadline < first);
+		entries.wakers.insert((deadline, id), waker.clone());
+		*key = Some((deadline, id));
+
+		if is_first {
+			self.wakeup.notify_one();
+		}
+	}
+
+	fn deregister(&self, key: Key) {
+		self.lock().wakers.remove(&key);
+	}
+}
+
+/// Future that resolves once the deadline has been reached
+#[derive(Debug)]
+pub(crate) struct Sleep {
+	deadline: Instant,
+	key: Option<Key>,
+}
+
+impl Sleep {
+	pub(crate) fn until(deadline: Instant) -> Self {
+		Self { deadline, key: None }
+	}
+}
+
+impl Future for Sleep {
+	type Output = ();
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		if Instant::now() >= self.deadline {
+			if let Some(key) = self.key.take() {
+				Timers::get().deregister(key);
+			}
+
+			return Poll::Ready(());
+		}
+
+		let deadline = self.deadline;
+		Timers::get().register(&mut self.key, deadline, cx.waker());
+
+		Poll::Pending
+	}
+}
+
+impl Drop for Sleep {
+	fn drop(&mut self) {
+		if let Some(key) = self.key.take() {
+			Timers::get().deregister(key);
+		}
+	}
+}
+
+/// Runs the future until it completes or the deadline is reached,
+/// whichever comes first. Without a deadline the future runs to completion
+pub(crate) fn timeout<F>(deadline: Option<Instant>, future: F) -> Timeout<F> {
+	Timeout { future, sleep: deadline.map(Sleep::until) }
+}
+
+/// Future returned by [timeout], resolving with `None` once the deadline
+/// is reached
+#[derive(Debug)]
+pub(crate) struct Timeout<F> {
+	future: F,
+	sleep: Option<Sleep>,
+}
+
+impl<F: Future + Unpin> Future for Timeout<F> {
+	type Output = Option<F::Output>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		if let Poll::Ready(output) = Pin::new(&mut self.future).poll(cx) {
+			return Poll::Ready(Some(output));
+		}
+
+		match &mut self.sleep {
+			Some(sleep) => Pin::new(sleep).poll(cx).map(|()| None),
+			None => Poll::Pending,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use super::*;
+
+	#[test]
+	fn sleep() {
+		let start = Instant::now();
+		smol::block_on(Sleep::until(start + Duration::from_millis(20)));
+		assert!(start.elapsed() >= Duration::from_millis(20));
+	}
+
+	#[test]
+	fn timeout_elapsed() {
+		let deadline = Instant::now() + Duration::from_millis(10);
+		assert!(smol::block_on(timeout(Some(deadline), std::future::pending::<()>())).is_none());
+	}
+}
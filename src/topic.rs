@@ -0,0 +1,245 @@
+use alloc::{collections::BTreeMap, sync::Arc};
+use core::{
+	fmt,
+	future::Future,
+	mem,
+	pin::Pin,
+	task::{Context, Poll},
+};
+
+use crate::{
+	Customer, OwnedRequestFuture, RequestError, SendReport, Vendor,
+	sync::{AtomicUsize, Mutex, MutexGuard, Ordering},
+};
+
+/// Vendor publishing resources under keys, so that each [`TopicCustomer`]
+/// only gets the resources of the key it asked for
+///
+/// Queues are created on the first request for a key
+/// and removed once nobody waits on them
+#[derive(Debug)]
+pub struct TopicVendor<K, T> {
+	topics: Arc<Topics<K, T>>,
+}
+
+/// Customer can request resource of a key from the linked [`TopicVendor`]
+#[derive(Debug)]
+pub struct TopicCustomer<K, T> {
+	topics: Arc<Topics<K, T>>,
+}
+
+#[derive(Debug)]
+struct Topics<K, T> {
+	state: Mutex<State<K, T>>,
+	vendors: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct State<K, T> {
+	topics: BTreeMap<K, Topic<T>>,
+	closed: bool,
+}
+
+/// Queue of a single key, kept alive while someone is interested in it
+#[derive(Debug)]
+struct Topic<T> {
+	vendor: Vendor<T>,
+	interested: usize,
+}
+
+impl<K, T> Topics<K, T> {
+	fn lock(&self) -> MutexGuard<'_, State<K, T>> {
+		self.state.lock()
+	}
+
+	fn close(&self) -> bool {
+		let topics = {
+			let mut state = self.lock();
+
+			if state.closed {
+				return false;
+			}
+
+			state.closed = true;
+			mem::take(&mut state.topics)
+		};
+
+		drop(topics);
+		true
+	}
+}
+
+impl<K, T> Clone for TopicVendor<K, T> {
+	fn clone(&self) -> Self {
+		self.topics.vendors.fetch_add(1, Ordering::Relaxed);
+		Self { topics: self.topics.clone() }
+	}
+}
+
+impl<K, T> Default for TopicVendor<K, T> {
+	fn default() -> Self {
+		let topics =
+			Topics { state: Mutex::new(State { topics: BTreeMap::new(), closed: false }), vendors: AtomicUsize::new(1) };
+
+		Self { topics: Arc::new(topics) }
+	}
+}
+
+impl<K, T> Drop for TopicVendor<K, T> {
+	fn drop(&mut self) {
+		if self.topics.vendors.fetch_sub(1, Ordering::AcqRel) == 1 {
+			self.topics.close();
+		}
+	}
+}
+
+impl<K, T> TopicVendor<K, T>
+where
+	K: Ord,
+{
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Creates a customer linked to this [`TopicVendor`]
+	pub fn customer(&self) -> TopicCustomer<K, T> {
+		TopicCustomer { topics: self.topics.clone() }
+	}
+
+	/// Sends the resource to customers waiting for the key,
+	/// same as [`Vendor::send`]
+	pub fn send(&self, key: &K, resource: T) -> SendReport
+	where
+		T: Clone,
+	{
+		let vendor = self.topics.lock().topics.get(key).map(|topic| topic.vendor.clone());
+		vendor.map(|vendor| vendor.send(resource)).unwrap_or_default()
+	}
+
+	pub fn waiters_count(&self, key: &K) -> usize {
+		self.topics.lock().topics.get(key).map_or(0, |topic| topic.vendor.waiters_count())
+	}
+
+	/// Returns `true` if someone waits for the key
+	pub fn has_waiters(&self, key: &K) -> bool {
+		self.waiters_count(key) > 0
+	}
+
+	/// Number of keys someone is interested in
+	pub fn topics_count(&self) -> usize {
+		self.topics.lock().topics.len()
+	}
+
+	/// Closes all queues, waking all waiting [`TopicCustomer`]s with
+	/// [`RequestError::Closed`]. Any further requests fail the same way.
+	///
+	/// Returns `true` if this call closed the queues
+	pub fn close(&self) -> bool {
+		self.topics.close()
+	}
+
+	pub fn is_closed(&self) -> bool {
+		self.topics.lock().closed
+	}
+}
+
+impl<K, T> Clone for TopicCustomer<K, T> {
+	fn clone(&self) -> Self {
+		Self { topics: self.topics.clone() }
+	}
+}
+
+impl<K, T> TopicCustomer<K, T>
+where
+	K: Ord + Clone,
+{
+	/// Queuing up for a resource published under the key
+	///
+	/// The request is queued as soon as it is created, see
+	/// [`TopicRequestFuture`]
+	///
+	/// # Errors
+	/// Same as [`Customer::request`]
+	pub fn request(&self, key: K) -> TopicRequestFuture<'_, K, T> {
+		let request = self.interest(key).map(|(customer, interest)| (customer.into_request(), interest));
+		TopicRequestFuture { request }
+	}
+
+	fn interest(&self, key: K) -> Result<(Customer<T>, Interest<'_, K, T>), RequestError> {
+		let mut state = self.topics.lock();
+
+		if state.closed {
+			return Err(RequestError::Closed);
+		}
+
+		let topic = state.topics.entry(key.clone()).or_insert_with(|| Topic { vendor: Vendor::new(), interested: 0 });
+		topic.interested += 1;
+
+		Ok((topic.vendor.customer(), Interest { topics: &self.topics, key }))
+	}
+
+	/// Returns `true` if the linked [`TopicVendor`]s have been closed or dropped
+	pub fn is_closed(&self) -> bool {
+		self.topics.lock().closed
+	}
+}
+
+/// Removes the queue of the key once nobody is interested in it
+struct Interest<'a, K, T>
+where
+	K: Ord,
+{
+	topics: &'a Topics<K, T>,
+	key: K,
+}
+
+impl<K, T> Drop for Interest<'_, K, T>
+where
+	K: Ord,
+{
+	fn drop(&mut self) {
+		let mut state = self.topics.lock();
+
+		if let Some(topic) = state.topics.get_mut(&self.key) {
+			topic.interested -= 1;
+
+			if topic.interested == 0 {
+				state.topics.remove(&self.key);
+			}
+		}
+	}
+}
+
+/// Future of a request for a key, see [`TopicCustomer::request`]
+///
+/// Same as [`RequestFuture`](crate::RequestFuture), the request takes its
+/// place in the queue of the key as soon as the future is created.
+/// Dropping the future leaves the queue
+#[must_use = "dropping the request leaves the queue"]
+pub struct TopicRequestFuture<'a, K, T>
+where
+	K: Ord,
+{
+	// The request leaves the queue before the interest in the key is dropped
+	request: Result<(OwnedRequestFuture<T>, Interest<'a, K, T>), RequestError>,
+}
+
+// The resource is never pinned, only moved out of the future
+impl<K: Ord, T> Unpin for TopicRequestFuture<'_, K, T> {}
+
+impl<K: Ord, T> Future for TopicRequestFuture<'_, K, T> {
+	type Output = Result<T, RequestError>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		match &mut self.request {
+			Ok((request, _)) => Pin::new(request).poll(cx),
+			Err(err) => Poll::Ready(Err(err.clone())),
+		}
+	}
+}
+
+impl<K: Ord, T> fmt::Debug for TopicRequestFuture<'_, K, T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("TopicRequestFuture").finish_non_exhaustive()
+	}
+}
@@ -0,0 +1,403 @@
+use alloc::{boxed::Box, collections::BTreeMap, sync::Arc, vec::Vec};
+use core::{cmp::Reverse, fmt, mem, time::Duration};
+
+use event_listener::{Event, EventListener};
+
+use crate::{
+	RequestError,
+	cache::Cache,
+	oneshot::Sender,
+	subscription::Feed,
+	sync::{AtomicUsize, Mutex, MutexGuard, Ordering},
+};
+
+pub(crate) type Responder<T> = Sender<Result<T, RequestError>>;
+
+/// Position of a waiter in the queue: higher priorities come first,
+/// then earlier arrivals
+type Key = (Reverse<i32>, u64);
+
+/// Predicate a resource has to satisfy to be accepted by a waiter
+pub(crate) type Filter<T> = Box<dyn Fn(&T) -> bool + Send>;
+
+/// What a bounded [Vendor](crate::Vendor) does with a
+/// [Customer](crate::Customer) arriving at a full queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+	/// The new request fails with [`RequestError::Full`]
+	#[default]
+	Reject,
+	/// The oldest waiter of the lowest priority fails with
+	/// [`RequestError::Evicted`] to make room. A new request of a lower
+	/// priority than every queued waiter is evicted right away instead
+	EvictOldest,
+	/// The new request waits until there is room in the queue
+	Wait,
+}
+
+/// Queue of waiting customers, where every waiter can be removed by its key
+/// once the customer is no longer interested in the resource
+#[derive(Debug)]
+pub(crate) struct Waiters<T, Q = ()> {
+	queue: Mutex<Queue<T, Q>>,
+	vendors: AtomicUsize,
+	capacity: Option<usize>,
+	overflow: Overflow,
+	space: Event,
+	arrival: Event,
+}
+
+#[derive(Debug)]
+struct Queue<T, Q> {
+	next_key: u64,
+	senders: BTreeMap<Key, Waiter<T, Q>>,
+	subscribers: BTreeMap<u64, Arc<Feed<T>>>,
+	closed: bool,
+	cache: Option<Cache<T>>,
+}
+
+impl<T, Q> Queue<T, Q> {
+	/// Returns `true` if a waiter of the given priority would be served
+	/// after every queued waiter
+	fn is_last(&self, priority: i32) -> bool {
+		self.senders.last_key_value().is_some_and(|(&(Reverse(lowest), _), _)| priority < lowest)
+	}
+
+	/// Removes the oldest waiter of the lowest priority
+	fn evict(&mut self) -> Option<Waiter<T, Q>> {
+		let (&(lowest, _), _) = self.senders.last_key_value()?;
+		let key = self.senders.range((lowest, 0)..).next().map(|(&key, _)| key)?;
+
+		self.senders.remove(&key)
+	}
+}
+
+impl<T, Q> Default for Waiters<T, Q> {
+	fn default() -> Self {
+		Self::new(None, Overflow::default())
+	}
+}
+
+impl<T, Q> Waiters<T, Q> {
+	fn new(capacity: Option<usize>, overflow: Overflow) -> Self {
+		Self {
+			queue: Mutex::new(Queue {
+				next_key: 0,
+				senders: BTreeMap::new(),
+				subscribers: BTreeMap::new(),
+				closed: false,
+				cache: None,
+			}),
+			vendors: AtomicUsize::new(0),
+			capacity,
+			overflow,
+			space: Event::new(),
+			arrival: Event::new(),
+		}
+	}
+
+	pub(crate) fn bounded(capacity: usize, overflow: Overflow) -> Self {
+		Self::new(Some(capacity), overflow)
+	}
+
+	fn lock(&self) -> MutexGuard<'_, Queue<T, Q>> {
+		self.queue.lock()
+	}
+
+	/// Same as [`Waiters::admit`], but fails with [`RequestError::Full`]
+	/// instead of waiting for room
+	pub(crate) fn try_register(self: &Arc<Self>, waiter: Waiter<T, Q>) -> Result<Registration<T, Q>, RequestError> {
+		match self.admit(waiter)? {
+			Admission::Admitted(registration) => Ok(registration),
+			Admission::Wait(..) => Err(RequestError::Full),
+		}
+	}
+
+	/// Puts the waiter at the end of the queue, or hands it back with a
+	/// listener to wait for room if the queue is bounded with [`Overflow::Wait`].
+	/// The waiter stays in the queue until it is popped or the returned
+	/// [Registration] is dropped
+	pub(crate) fn admit(self: &Arc<Self>, waiter: Waiter<T, Q>) -> Result<Admission<T, Q>, RequestError> {
+		let mut queue = self.lock();
+
+		if queue.closed {
+			return Err(RequestError::Closed);
+		}
+
+		let mut evicted = None;
+
+		if self.capacity.is_some_and(|capacity| queue.senders.len() >= capacity) {
+			match self.overflow {
+				Overflow::Reject => return Err(RequestError::Full),
+				Overflow::EvictOldest if queue.is_last(waiter.priority) => return Err(RequestError::Evicted),
+				Overflow::EvictOldest => evicted = queue.evict(),
+				Overflow::Wait => return Ok(Admission::Wait(waiter, self.space.listen())),
+			}
+		}
+
+		let key = (Reverse(waiter.priority), queue.next_key);
+		queue.next_key += 1;
+		queue.senders.insert(key, waiter);
+		drop(queue);
+
+		self.arrival.notify(usize::MAX);
+
+		if let Some(evicted) = evicted {
+			let _ = evicted.responder.send(Err(RequestError::Evicted));
+		}
+
+		Ok(Admission::Admitted(Registration { waiters: self.clone(), key }))
+	}
+
+	/// Takes the first waiter accepting the resource out of the queue,
+	/// serving higher priorities first
+	pub(crate) fn pop(&self, resource: &T) -> Option<Responder<T>> {
+		let sender = {
+			let mut queue = self.lock();
+			let key = queue.senders.iter().find(|(_, waiter)| waiter.accepts(Some(resource))).map(|(&key, _)| key);
+			key.and_then(|key| queue.senders.remove(&key)).map(|waiter| waiter.responder)
+		};
+
+		if sender.is_some() {
+			self.space.notify_additional(1);
+		}
+
+		sender
+	}
+
+	/// Takes every waiter accepting the resource out of the queue at once,
+	/// so that the queue is served as a consistent snapshot even with
+	/// concurrent vendors. Without a resource only waiters without a predicate
+	/// are taken. Subscribers stay in the queue
+	pub(crate) fn drain(&self, resource: Option<&T>) -> Drained<T, Q> {
+		let (requests, subscribers) = {
+			let mut queue = self.lock();
+			let requests: Vec<_> = queue
+				.senders
+				.extract_if(.., |_, waiter| waiter.accepts(resource))
+				.map(|(_, waiter)| (waiter.query, waiter.responder))
+				.collect();
+
+			(requests, queue.subscribers.values().cloned().collect())
+		};
+
+		self.space.notify_additional(requests.len());
+
+		Drained { requests, subscribers }
+	}
+
+	/// Takes every waiter and subscriber out of the queue regardless of
+	/// its predicate
+	pub(crate) fn take_all(&self) -> Drained<T, Q> {
+		let (senders, subscribers) = {
+			let mut queue = self.lock();
+			(mem::take(&mut queue.senders), mem::take(&mut queue.subscribers))
+		};
+
+		self.space.notify_additional(senders.len());
+
+		Drained {
+			requests: senders.into_values().map(|waiter| (waiter.query, waiter.responder)).collect(),
+			subscribers: subscribers.into_values().collect(),
+		}
+	}
+
+	/// Adds a subscriber that stays in the queue until it is unsubscribed.
+	/// Returns `None` if the queue is closed
+	pub(crate) fn subscribe(&self, feed: Arc<Feed<T>>) -> Option<u64> {
+		let mut queue = self.lock();
+
+		if queue.closed {
+			return None;
+		}
+
+		let key = queue.next_key;
+		queue.next_key += 1;
+		queue.subscribers.insert(key, feed);
+		drop(queue);
+
+		self.arrival.notify(usize::MAX);
+
+		Some(key)
+	}
+
+	pub(crate) fn unsubscribe(&self, key: u64) {
+		self.lock().subscribers.remove(&key);
+	}
+
+	/// Starts remembering the last sent resource
+	pub(crate) fn enable_cache(&self, max_age: Option<Duration>)
+	where
+		T: Clone,
+	{
+		self.lock().cache = Some(Cache::new(max_age));
+	}
+
+	/// Stores the resource built by `resource` if the cache is enabled
+	pub(crate) fn remember(&self, resource: impl FnOnce() -> T) {
+		if self.lock().cache.is_none() {
+			return;
+		}
+
+		let resource = resource();
+
+		if let Some(cache) = &mut self.lock().cache {
+			cache.store(resource);
+		}
+	}
+
+	pub(crate) fn cached(&self) -> Option<T> {
+		self.lock().cache.as_ref()?.get()
+	}
+
+	/// Same as [`Waiters::cached`], but only from a cache with a max age
+	pub(crate) fn cached_fresh(&self) -> Option<T> {
+		self.lock().cache.as_ref()?.get_fresh()
+	}
+
+	/// Waits until at least `n` waiters are in the queue.
+	/// Returns `false` if the queue got closed instead
+	pub(crate) async fn wait_for(&self, n: usize) -> bool {
+		self.wait_until(|queue| queue.senders.len() + queue.subscribers.len() >= n).await
+	}
+
+	/// Number of customers that have joined the queue so far
+	pub(crate) fn arrivals(&self) -> u64 {
+		self.lock().next_key
+	}
+
+	/// Waits until someone joins the queue after the given number of
+	/// [arrivals](Waiters::arrivals). Returns `false` if the queue got closed
+	pub(crate) async fn wait_for_arrival(&self, since: u64) -> bool {
+		self.wait_until(|queue| queue.next_key > since).await
+	}
+
+	async fn wait_until(&self, ready: impl Fn(&Queue<T, Q>) -> bool) -> bool {
+		loop {
+			let listener = self.arrival.listen();
+
+			{
+				let queue = self.lock();
+
+				if queue.closed {
+					return false;
+				}
+
+				if ready(&queue) {
+					return true;
+				}
+			}
+
+			listener.await;
+		}
+	}
+
+	pub(crate) fn len(&self) -> usize {
+		let queue = self.lock();
+		queue.senders.len() + queue.subscribers.len()
+	}
+
+	pub(crate) fn is_closed(&self) -> bool {
+		self.lock().closed
+	}
+
+	/// Rejects all current and future waiters.
+	/// Returns `true` if this call closed the queue
+	pub(crate) fn close(&self) -> bool {
+		let (senders, subscribers) = {
+			let mut queue = self.lock();
+
+			if queue.closed {
+				return false;
+			}
+
+			queue.closed = true;
+			(mem::take(&mut queue.senders), mem::take(&mut queue.subscribers))
+		};
+
+		self.space.notify(usize::MAX);
+		self.arrival.notify(usize::MAX);
+
+		for waiter in senders.into_values() {
+			let _ = waiter.responder.send(Err(RequestError::Closed));
+		}
+
+		for feed in subscribers.into_values() {
+			feed.close();
+		}
+
+		true
+	}
+
+	pub(crate) fn attach_vendor(&self) {
+		self.vendors.fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Closes the queue once the last vendor is gone
+	pub(crate) fn detach_vendor(&self) {
+		if self.vendors.fetch_sub(1, Ordering::AcqRel) == 1 {
+			self.close();
+		}
+	}
+}
+
+/// Customer waiting for a resource made for its query,
+/// optionally only for the one satisfying its predicate
+pub(crate) struct Waiter<T, Q> {
+	query: Q,
+	priority: i32,
+	responder: Responder<T>,
+	filter: Option<Filter<T>>,
+}
+
+impl<T, Q> Waiter<T, Q> {
+	pub(crate) fn new(query: Q, priority: i32, responder: Responder<T>, filter: Option<Filter<T>>) -> Self {
+		Self { query, priority, responder, filter }
+	}
+
+	fn accepts(&self, resource: Option<&T>) -> bool {
+		match (&self.filter, resource) {
+			(None, _) => true,
+			(Some(filter), Some(resource)) => filter(resource),
+			(Some(_), None) => false,
+		}
+	}
+}
+
+impl<T: fmt::Debug, Q: fmt::Debug> fmt::Debug for Waiter<T, Q> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Waiter")
+			.field("query", &self.query)
+			.field("priority", &self.priority)
+			.field("responder", &self.responder)
+			.field("filtered", &self.filter.is_some())
+			.finish()
+	}
+}
+
+/// Waiters taken out of the queue by [`Waiters::drain`] or
+/// [`Waiters::take_all`]
+pub(crate) struct Drained<T, Q> {
+	pub(crate) requests: Vec<(Q, Responder<T>)>,
+	pub(crate) subscribers: Vec<Arc<Feed<T>>>,
+}
+
+pub(crate) enum Admission<T, Q> {
+	Admitted(Registration<T, Q>),
+	Wait(Waiter<T, Q>, EventListener),
+}
+
+/// Removes the waiter from the queue on drop
+#[derive(Debug)]
+pub(crate) struct Registration<T, Q> {
+	waiters: Arc<Waiters<T, Q>>,
+	key: Key,
+}
+
+impl<T, Q> Drop for Registration<T, Q> {
+	fn drop(&mut self) {
+		if self.waiters.lock().senders.remove(&self.key).is_some() {
+			self.waiters.space.notify_additional(1);
+		}
+	}
+}
@@ -0,0 +1,80 @@
+#![cfg(ticque_loom)]
+
+use loom::{
+	future::block_on,
+	sync::{
+		Arc,
+		atomic::{AtomicBool, Ordering},
+	},
+	thread,
+};
+use smol::future;
+use ticque::Vendor;
+
+#[test]
+fn concurrent_vendors() {
+	loom::model(|| {
+		let vendor = Vendor::new();
+		let customer = vendor.customer();
+
+		let mut first = Box::pin(customer.request());
+		let mut second = Box::pin(customer.request());
+		assert!(block_on(future::poll_once(first.as_mut())).is_none());
+		assert!(block_on(future::poll_once(second.as_mut())).is_none());
+
+		let t1 = thread::spawn({
+			let vendor = vendor.clone();
+			move || vendor.send(1)
+		});
+
+		let report = vendor.send(2);
+		let Ok(other) = t1.join() else { panic!("vendor thread panicked") };
+
+		assert_eq!(report.delivered + other.delivered, 2);
+		assert!(matches!(block_on(first), Ok(1 | 2)));
+		assert!(matches!(block_on(second), Ok(1 | 2)));
+		assert!(!vendor.has_waiters());
+	});
+}
+
+#[test]
+fn customer_joins_during_send() {
+	loom::model(|| {
+		let vendor = Vendor::new();
+		let customer = vendor.customer();
+		let sent = Arc::new(AtomicBool::new(false));
+
+		let mut first = Box::pin(customer.request());
+		assert!(block_on(future::poll_once(first.as_mut())).is_none());
+
+		let t1 = thread::spawn({
+			let customer = customer.clone();
+			let sent = sent.clone();
+			move || {
+				let mut second = Box::pin(customer.request());
+
+				if let Some(received) = block_on(future::poll_once(second.as_mut())) {
+					return Some(received);
+				}
+
+				while !sent.load(Ordering::Acquire) {
+					thread::yield_now();
+				}
+
+				block_on(future::poll_once(second.as_mut()))
+			}
+		});
+
+		let report = vendor.send(1);
+		sent.store(true, Ordering::Release);
+		let Ok(second) = t1.join() else { panic!("customer thread panicked") };
+
+		assert!(matches!(block_on(first), Ok(1)));
+
+		match report.delivered {
+			1 => assert!(second.is_none()),
+			2 => assert!(matches!(second, Some(Ok(1)))),
+			delivered => panic!("unexpected number of deliveries: {delivered}"),
+		}
+	});
+}